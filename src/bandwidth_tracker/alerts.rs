@@ -0,0 +1,384 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    process::{Command, Stdio},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::config::{deserialize_duration, pattern::Pattern};
+
+use super::{
+    bytes::{parse_rate, BytesPerSecond},
+    measure::RateWindow,
+    tracker::BandwidthTracker,
+};
+
+/// Rates are averaged over a second before being compared to the thresholds, the ticks are too noisy
+const EVALUATION_WINDOW: RateWindow = RateWindow::Average(Duration::from_secs(1));
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertsConfig {
+    /// Shell command run when an alert fires, with the details in `NET_MONITOR_*` variables
+    pub hook: Option<String>,
+    pub rules: Vec<AlertRule>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlertTarget {
+    Process,
+    Interface,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Direction {
+    #[serde(alias = "received")]
+    Rx,
+    #[serde(alias = "send")]
+    Tx,
+    /// Received and send
+    #[default]
+    Total,
+}
+
+impl Direction {
+    fn pick(self, received: BytesPerSecond, send: BytesPerSecond) -> BytesPerSecond {
+        match self {
+            Direction::Rx => received,
+            Direction::Tx => send,
+            Direction::Total => received + send,
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::Rx => write!(f, "rx"),
+            Direction::Tx => write!(f, "tx"),
+            Direction::Total => write!(f, "total"),
+        }
+    }
+}
+
+/// "`on` matching `matching` has a `direction` rate above `above` for `during`"
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// Shown in the log and given to the hook, defaults to the position of the rule
+    pub name: Option<String>,
+    pub on: AlertTarget,
+    /// Glob or `/regex/` on the process name or on the interface, every one when missing
+    pub matching: Option<Pattern>,
+    #[serde(default)]
+    pub direction: Direction,
+    /// Rate like `50MB` or `900Mbit`, per second
+    #[serde(deserialize_with = "deserialize_rate")]
+    pub above: BytesPerSecond,
+    /// How long the rate stays above the threshold before the alert fires, like `10s`
+    #[serde(default, deserialize_with = "deserialize_duration")]
+    pub during: Duration,
+}
+
+fn deserialize_rate<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BytesPerSecond, D::Error> {
+    parse_rate(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AlertSubject {
+    Process { pid: i32, name: String },
+    Interface(String),
+}
+
+impl Display for AlertSubject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertSubject::Process { pid, name } => write!(f, "{name} ({pid})"),
+            AlertSubject::Interface(interface) => write!(f, "{interface}"),
+        }
+    }
+}
+
+/// A rule starting or stopping to be broken by a process or an interface
+pub struct Alert {
+    pub rule: String,
+    pub subject: AlertSubject,
+    pub direction: Direction,
+    pub rate: BytesPerSecond,
+    pub threshold: BytesPerSecond,
+    /// `false` when the rate went back below the threshold
+    pub firing: bool,
+}
+
+impl Display for Alert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.firing {
+            write!(
+                f,
+                "alert {}: {} {} at {} above {}",
+                self.rule, self.subject, self.direction, self.rate, self.threshold
+            )
+        } else {
+            write!(f, "resolved {}: {}", self.rule, self.subject)
+        }
+    }
+}
+
+/// Evaluates the alert rules on each tick of the tracker
+pub struct AlertMonitor {
+    rules: Vec<AlertRule>,
+    hook: Option<String>,
+    /// Since when each (rule, subject) is above the threshold, and whether its alert fired
+    breaches: HashMap<(usize, AlertSubject), (SystemTime, bool)>,
+}
+
+impl AlertMonitor {
+    pub fn new(config: AlertsConfig) -> AlertMonitor {
+        AlertMonitor {
+            rules: config.rules,
+            hook: config.hook,
+            breaches: HashMap::new(),
+        }
+    }
+
+    /// Alerts that fired or were resolved at the last tick, the hook is run for the fired ones
+    pub fn evaluate<'a>(
+        &mut self,
+        tracker: &BandwidthTracker,
+        name_of: impl Fn(i32) -> Option<&'a str>,
+    ) -> Vec<Alert> {
+        let now = tracker.last_tick();
+        let mut rates = HashMap::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            let matches = |name: &str| rule.matching.as_ref().is_none_or(|p| p.matches(name));
+            match rule.on {
+                AlertTarget::Process => {
+                    for (pid, received, send) in tracker.get_throughput_at(now, EVALUATION_WINDOW) {
+                        let Some(name) = name_of(pid).filter(|name| matches(name)) else {
+                            continue;
+                        };
+                        let subject = AlertSubject::Process {
+                            pid,
+                            name: name.to_string(),
+                        };
+                        rates.insert((index, subject), rule.direction.pick(received, send));
+                    }
+                }
+                AlertTarget::Interface => {
+                    for (interface, interface_rates) in
+                        tracker.get_interface_rates(EVALUATION_WINDOW)
+                    {
+                        if matches(&interface) {
+                            let rate = rule
+                                .direction
+                                .pick(interface_rates.received, interface_rates.send);
+                            rates.insert((index, AlertSubject::Interface(interface)), rate);
+                        }
+                    }
+                }
+            }
+        }
+        let breaking: HashSet<_> = rates
+            .iter()
+            .filter(|((index, _), rate)| **rate > self.rules[*index].above)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut alerts = Vec::new();
+        self.breaches.retain(|key, (_, fired)| {
+            let still_breaking = breaking.contains(key);
+            if !still_breaking && *fired {
+                let rate = rates.get(key).copied().unwrap_or_default();
+                alerts.push(self.rules[key.0].alert(key.0, key.1.clone(), rate, false));
+            }
+            still_breaking
+        });
+        for key in breaking {
+            let (since, fired) = self.breaches.entry(key.clone()).or_insert((now, false));
+            let rule = &self.rules[key.0];
+            if !*fired && now.duration_since(*since).unwrap_or_default() >= rule.during {
+                *fired = true;
+                let rate = rates[&key];
+                alerts.push(rule.alert(key.0, key.1, rate, true));
+            }
+        }
+
+        if let Some(hook) = &self.hook {
+            alerts
+                .iter()
+                .filter(|alert| alert.firing)
+                .for_each(|alert| run_hook(hook, alert));
+        }
+        alerts
+    }
+
+    pub fn is_firing_for_pid(&self, pid: i32) -> bool {
+        self.breaches.iter().any(|((_, subject), (_, fired))| {
+            *fired && matches!(subject, AlertSubject::Process { pid: firing, .. } if *firing == pid)
+        })
+    }
+
+    pub fn is_firing_for_interface(&self, interface: &str) -> bool {
+        self.breaches.iter().any(|((_, subject), (_, fired))| {
+            *fired && *subject == AlertSubject::Interface(interface.to_string())
+        })
+    }
+}
+
+impl AlertRule {
+    fn alert(
+        &self,
+        index: usize,
+        subject: AlertSubject,
+        rate: BytesPerSecond,
+        firing: bool,
+    ) -> Alert {
+        Alert {
+            rule: self
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("#{}", index + 1)),
+            subject,
+            direction: self.direction,
+            rate,
+            threshold: self.above,
+            firing,
+        }
+    }
+}
+
+/// Runs the hook in the background, its failures are ignored
+fn run_hook(hook: &str, alert: &Alert) {
+    let (kind, pid, name) = match &alert.subject {
+        AlertSubject::Process { pid, name } => ("process", pid.to_string(), name.clone()),
+        AlertSubject::Interface(interface) => ("interface", String::new(), interface.clone()),
+    };
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .env("NET_MONITOR_ALERT", &alert.rule)
+        .env("NET_MONITOR_KIND", kind)
+        .env("NET_MONITOR_PID", pid)
+        .env("NET_MONITOR_NAME", name)
+        .env("NET_MONITOR_DIRECTION", alert.direction.to_string())
+        .env("NET_MONITOR_RATE", format!("{:.0}", f64::from(alert.rate)))
+        .env(
+            "NET_MONITOR_THRESHOLD",
+            format!("{:.0}", f64::from(alert.threshold)),
+        )
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    if let Ok(mut child) = child {
+        // reap the hook without blocking the ticks
+        std::thread::spawn(move || child.wait());
+    }
+}
+
+#[cfg(test)]
+mod tests_alerts {
+    use std::time::{Duration, SystemTime};
+
+    use crate::bandwidth_tracker::{source::Snapshot, tracker::BandwidthTracker};
+
+    use super::{AlertMonitor, AlertTarget, AlertsConfig, Direction};
+
+    /// Ticks of (tick, firing) alerts of a process receiving `received[tick]` bytes at each tick of half a second
+    fn alerts_of(received: &[i64]) -> Vec<(usize, bool)> {
+        let config: AlertsConfig = toml::from_str(
+            r#"
+            [[rules]]
+            on = "process"
+            direction = "rx"
+            above = "1KB"
+            during = "2s"
+            "#,
+        )
+        .unwrap();
+        let mut monitor = AlertMonitor::new(config);
+        let mut tracker = BandwidthTracker::new();
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut total = 0;
+        let mut alerts = Vec::new();
+        for (tick, bytes) in received.iter().enumerate() {
+            total += bytes;
+            tracker.ingest(&Snapshot {
+                at: start + Duration::from_millis(500 * tick as u64),
+                processes: vec![(42, total.into(), 0.into())],
+                interfaces: Vec::new(),
+                connections: Vec::new(),
+                identities: Vec::new(),
+            });
+            let fired = monitor.evaluate(&tracker, |_| Some("curl"));
+            alerts.extend(fired.iter().map(|alert| (tick, alert.firing)));
+        }
+        alerts
+    }
+
+    #[test]
+    fn test_alert_fires_after_during_and_resolves() {
+        // 2KB/s from the second tick, back to nothing at the tenth
+        let mut received = vec![0];
+        received.extend([1000; 8]);
+        received.extend([0; 3]);
+
+        assert_eq!(alerts_of(&received), [(5, true), (9, false)]);
+    }
+
+    #[test]
+    fn test_short_breaches_do_not_fire() {
+        // a dip below the threshold starts the `during` over
+        let received = [0, 1000, 1000, 1000, 0, 1000, 1000, 1000, 0];
+
+        assert!(alerts_of(&received).is_empty());
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let config: AlertsConfig = toml::from_str(
+            r#"
+            hook = "notify-send net-monitor"
+
+            [[rules]]
+            on = "process"
+            above = "50MB/s"
+            during = "10s"
+
+            [[rules]]
+            name = "uplink"
+            on = "interface"
+            matching = "eth0"
+            direction = "tx"
+            above = "900Mbit"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.rules[0].on, AlertTarget::Process);
+        assert!(config.rules[0].above == 50_000_000.0.into());
+        assert_eq!(config.rules[0].during, Duration::from_secs(10));
+        assert_eq!(config.rules[1].direction, Direction::Tx);
+        assert!(config.rules[1].above == 112_500_000.0.into());
+    }
+
+    #[test]
+    fn test_invalid_rate() {
+        let config = toml::from_str::<AlertsConfig>(
+            r#"
+            [[rules]]
+            on = "process"
+            above = "fast"
+            "#,
+        );
+
+        assert!(config.is_err());
+    }
+}
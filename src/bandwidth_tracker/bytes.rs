@@ -4,23 +4,44 @@ use std::{
     time::Duration,
 };
 
-use byte_unit::Byte;
+use byte_unit::{Bit, Byte, Unit, UnitType};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Default, Debug)]
-pub struct NumberOfBytes(i32);
+/// Wide enough for the usage of a month
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NumberOfBytes(i64);
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq, PartialOrd)]
 pub struct BytesPerSecond(f64);
 
 impl From<i32> for NumberOfBytes {
     fn from(value: i32) -> Self {
+        NumberOfBytes(value.into())
+    }
+}
+
+impl From<u32> for NumberOfBytes {
+    fn from(value: u32) -> Self {
+        NumberOfBytes(value.into())
+    }
+}
+
+impl From<i64> for NumberOfBytes {
+    fn from(value: i64) -> Self {
         NumberOfBytes(value)
     }
 }
 
+impl From<NumberOfBytes> for i64 {
+    fn from(value: NumberOfBytes) -> Self {
+        value.0
+    }
+}
+
 impl From<u64> for NumberOfBytes {
     fn from(value: u64) -> Self {
-        NumberOfBytes(value as i32)
+        NumberOfBytes(value as i64)
     }
 }
 
@@ -42,11 +63,7 @@ impl Sub for NumberOfBytes {
 
 impl Display for NumberOfBytes {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            Byte::from_u64(self.0 as u64).get_appropriate_unit(byte_unit::UnitType::Decimal)
-        )
+        write!(f, "{}", UnitFormat::default().bytes(*self))
     }
 }
 
@@ -60,17 +77,37 @@ impl BytesPerSecond {
             BytesPerSecond(bytes.0 as f64 / duration_as_millis as f64 * 1000.0)
         }
     }
+
+    pub fn max(self, other: BytesPerSecond) -> BytesPerSecond {
+        BytesPerSecond(self.0.max(other.0))
+    }
+}
+
+impl Add for BytesPerSecond {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        BytesPerSecond(self.0 + rhs.0)
+    }
+}
+
+impl Sub for BytesPerSecond {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        BytesPerSecond(self.0 - rhs.0)
+    }
 }
 
 impl Display for BytesPerSecond {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}/s",
-            Byte::from_f64(self.0)
-                .unwrap_or_default()
-                .get_appropriate_unit(byte_unit::UnitType::Decimal)
-        )
+        write!(f, "{}", UnitFormat::default().rate(*self))
+    }
+}
+
+impl From<f64> for BytesPerSecond {
+    fn from(value: f64) -> Self {
+        BytesPerSecond(value)
     }
 }
 
@@ -85,3 +122,158 @@ impl From<BytesPerSecond> for f64 {
         value.0
     }
 }
+
+/// Parses an amount like `5GB`, `512KiB` or `900Mbit`
+///
+/// A unit ending with a lowercase `b`, like `Mb`, is refused: the tables write bits this way, but it usually
+/// means bytes in a config file.
+pub fn parse_amount(amount: &str) -> Result<NumberOfBytes, String> {
+    let unit = amount.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == ' ');
+    if unit.ends_with('b') {
+        return Err(format!(
+            "ambiguous unit in `{amount}`, write `MB` for bytes or `Mbit` for bits"
+        ));
+    }
+    Byte::parse_str(amount, true)
+        .map(|bytes| NumberOfBytes::from(bytes.as_u64()))
+        .map_err(|_| format!("invalid amount `{amount}`"))
+}
+
+/// Parses a rate like `50MB` or `900Mbit/s`, the amount per second
+pub fn parse_rate(rate: &str) -> Result<BytesPerSecond, String> {
+    let amount = rate.strip_suffix("/s").unwrap_or(rate);
+    parse_amount(amount).map(|bytes| BytesPerSecond::from(f64::from(bytes)))
+}
+
+/// How the amounts of data are written, decimal bytes with the most appropriate unit by default
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UnitFormat {
+    /// Bits instead of bytes, like `Mbit/s`
+    pub bits: bool,
+    /// Powers of 1024 (`KiB`, `MiB`) instead of powers of 1000 (`KB`, `MB`)
+    pub binary: bool,
+    /// Always write this unit instead of the most appropriate one, it decides between bits and bytes
+    pub fixed_unit: Option<Unit>,
+    /// Plain number of bytes (or bits) without unit, for scripts
+    pub raw: bool,
+}
+
+impl UnitFormat {
+    pub fn bytes(&self, bytes: NumberOfBytes) -> String {
+        self.amount(bytes.0 as f64)
+    }
+
+    pub fn rate(&self, rate: BytesPerSecond) -> String {
+        let sign = if rate.0 < 0.0 { "-" } else { "" };
+        if self.raw {
+            format!("{sign}{}", self.amount(rate.0.abs()))
+        } else {
+            format!("{sign}{}/s", self.amount(rate.0.abs()))
+        }
+    }
+
+    fn amount(&self, bytes: f64) -> String {
+        let unit_type = if self.binary {
+            UnitType::Binary
+        } else {
+            UnitType::Decimal
+        };
+        let in_bits = self.fixed_unit.map_or(self.bits, Unit::is_bit);
+
+        match (self.raw, self.fixed_unit) {
+            (true, _) if in_bits => format!("{:.0}", bytes * 8.),
+            (true, _) => format!("{bytes:.0}"),
+            // a fixed number of decimals keeps the columns aligned
+            (false, Some(unit)) if in_bits => format!(
+                "{:.2}",
+                Bit::from_f64(bytes * 8.)
+                    .unwrap_or_default()
+                    .get_adjusted_unit(unit)
+            ),
+            (false, Some(unit)) => format!(
+                "{:.2}",
+                Byte::from_f64(bytes)
+                    .unwrap_or_default()
+                    .get_adjusted_unit(unit)
+            ),
+            (false, None) if in_bits => Bit::from_f64(bytes * 8.)
+                .unwrap_or_default()
+                .get_appropriate_unit(unit_type)
+                .to_string(),
+            (false, None) => Byte::from_f64(bytes)
+                .unwrap_or_default()
+                .get_appropriate_unit(unit_type)
+                .to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_unit_format {
+    use std::time::Duration;
+
+    use byte_unit::Unit;
+
+    use super::{parse_amount, parse_rate, BytesPerSecond, NumberOfBytes, UnitFormat};
+
+    fn rate(bytes: i32) -> BytesPerSecond {
+        BytesPerSecond::new(NumberOfBytes::from(bytes), Duration::from_secs(1))
+    }
+
+    #[test]
+    fn test_default_is_decimal_bytes() {
+        assert_eq!(UnitFormat::default().rate(rate(1_500_000)), "1.5 MB/s");
+        assert_eq!(UnitFormat::default().rate(rate(-2_000)), "-2 KB/s");
+    }
+
+    #[test]
+    fn test_bits_and_binary() {
+        let bits = UnitFormat {
+            bits: true,
+            ..Default::default()
+        };
+        assert_eq!(bits.rate(rate(1_000_000)), "8 Mb/s");
+
+        let binary = UnitFormat {
+            binary: true,
+            ..Default::default()
+        };
+        assert_eq!(binary.bytes(NumberOfBytes::from(2048)), "2 KiB");
+    }
+
+    #[test]
+    fn test_fixed_unit_and_raw() {
+        let fixed = UnitFormat {
+            fixed_unit: Some(Unit::Mbit),
+            ..Default::default()
+        };
+        assert_eq!(fixed.rate(rate(125_000)), "1.00 Mb/s");
+
+        let raw = UnitFormat {
+            raw: true,
+            bits: true,
+            ..Default::default()
+        };
+        assert_eq!(raw.rate(rate(1_000)), "8000");
+    }
+
+    #[test]
+    fn test_parse_amounts_and_rates() {
+        assert_eq!(
+            parse_amount("5GB"),
+            Ok(NumberOfBytes::from(5_000_000_000u64))
+        );
+        assert_eq!(parse_amount("2 KiB"), Ok(NumberOfBytes::from(2048)));
+        assert_eq!(parse_amount("42"), Ok(NumberOfBytes::from(42)));
+        assert!(parse_rate("900Mbit/s") == Ok(BytesPerSecond::from(112_500_000.)));
+        assert!(parse_rate("1MB/s") == Ok(BytesPerSecond::from(1_000_000.)));
+        assert!(parse_amount("fast").is_err());
+    }
+
+    #[test]
+    fn test_lowercase_b_is_ambiguous() {
+        assert!(parse_amount("100Mb").is_err());
+        assert!(parse_rate("8kb/s").is_err());
+        assert!(parse_amount("100b").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use std::{ops::Sub, time::Duration};
+
+use procfs::net::DeviceStatus;
+use serde::{Deserialize, Serialize};
+
+use super::bytes::BytesPerSecond;
+
+/// Packet counters of an interface from `/proc/net/dev`, the bytes are tracked separately
+#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize)]
+pub struct InterfaceCounters {
+    pub received_packets: u64,
+    pub send_packets: u64,
+    pub errors: u64,
+    pub drops: u64,
+    pub fifo: u64,
+    pub multicast: u64,
+}
+
+impl From<&DeviceStatus> for InterfaceCounters {
+    fn from(status: &DeviceStatus) -> Self {
+        InterfaceCounters {
+            received_packets: status.recv_packets,
+            send_packets: status.sent_packets,
+            errors: status.recv_errs + status.sent_errs,
+            drops: status.recv_drop + status.sent_drop,
+            fifo: status.recv_fifo + status.sent_fifo,
+            multicast: status.recv_multicast,
+        }
+    }
+}
+
+impl Sub for InterfaceCounters {
+    type Output = Self;
+
+    /// Counters are reset when an interface is re-created, saturate instead of underflowing
+    fn sub(self, rhs: Self) -> Self::Output {
+        InterfaceCounters {
+            received_packets: self.received_packets.saturating_sub(rhs.received_packets),
+            send_packets: self.send_packets.saturating_sub(rhs.send_packets),
+            errors: self.errors.saturating_sub(rhs.errors),
+            drops: self.drops.saturating_sub(rhs.drops),
+            fifo: self.fifo.saturating_sub(rhs.fifo),
+            multicast: self.multicast.saturating_sub(rhs.multicast),
+        }
+    }
+}
+
+/// Per second rates of all the counters of an interface
+#[derive(Clone, Copy, Default)]
+pub struct InterfaceRates {
+    pub received: BytesPerSecond,
+    pub send: BytesPerSecond,
+    pub received_packets: f64,
+    pub send_packets: f64,
+    pub errors: f64,
+    pub drops: f64,
+    pub fifo: f64,
+    pub multicast: f64,
+}
+
+impl InterfaceRates {
+    pub fn new(
+        received: BytesPerSecond,
+        send: BytesPerSecond,
+        counters: InterfaceCounters,
+        duration: Duration,
+    ) -> InterfaceRates {
+        let seconds = duration.as_secs_f64();
+        let per_second = |count: u64| {
+            if seconds == 0. {
+                0.
+            } else {
+                count as f64 / seconds
+            }
+        };
+
+        InterfaceRates {
+            received,
+            send,
+            received_packets: per_second(counters.received_packets),
+            send_packets: per_second(counters.send_packets),
+            errors: per_second(counters.errors),
+            drops: per_second(counters.drops),
+            fifo: per_second(counters.fifo),
+            multicast: per_second(counters.multicast),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+use serde::Deserialize;
+
+/// Period over which a throughput is measured
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum RateWindow {
+    /// Between the last two ticks
+    Instant,
+    /// Average over the duration before the tick
+    Average(Duration),
+}
+
+impl RateWindow {
+    /// Windows offered by the TUI, in the order they are cycled through
+    pub const ALL: [RateWindow; 5] = [
+        RateWindow::Instant,
+        RateWindow::Average(Duration::from_secs(1)),
+        RateWindow::Average(Duration::from_secs(5)),
+        RateWindow::Average(Duration::from_secs(30)),
+        RateWindow::Average(Duration::from_secs(5 * 60)),
+    ];
+
+    pub fn next(self) -> RateWindow {
+        let index = Self::ALL
+            .iter()
+            .position(|window| *window == self)
+            .map_or(0, |index| index + 1);
+        Self::ALL[index % Self::ALL.len()]
+    }
+}
+
+impl Default for RateWindow {
+    fn default() -> Self {
+        RateWindow::Average(Duration::from_secs(5))
+    }
+}
+
+impl Display for RateWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateWindow::Instant => write!(f, "instant"),
+            RateWindow::Average(duration) if duration.as_secs() >= 60 => {
+                write!(f, "{}m", duration.as_secs() / 60)
+            }
+            RateWindow::Average(duration) => write!(f, "{}s", duration.as_secs()),
+        }
+    }
+}
+
+impl FromStr for RateWindow {
+    type Err = String;
+
+    fn from_str(window: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|known| known.to_string() == window)
+            .ok_or_else(|| {
+                let known: Vec<_> = Self::ALL.iter().map(RateWindow::to_string).collect();
+                format!(
+                    "unknown rate window `{window}`, expected one of {}",
+                    known.join(", ")
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for RateWindow {
+    type Error = String;
+
+    fn try_from(window: String) -> Result<Self, Self::Error> {
+        window.parse()
+    }
+}
+
+/// Origin of the byte totals
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TotalsSince {
+    /// When the monitoring started
+    #[default]
+    Start,
+    /// The last call to `BandwidthTracker::reset_totals`
+    Reset,
+}
+
+impl Display for TotalsSince {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TotalsSince::Start => write!(f, "since start"),
+            TotalsSince::Reset => write!(f, "since reset"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_measure {
+    use std::time::Duration;
+
+    use super::RateWindow;
+
+    #[test]
+    fn test_rate_window_round_trip() {
+        for window in RateWindow::ALL {
+            assert_eq!(window.to_string().parse(), Ok(window));
+        }
+        assert_eq!(
+            "5m".parse(),
+            Ok(RateWindow::Average(Duration::from_secs(300)))
+        );
+        assert!("2h".parse::<RateWindow>().is_err());
+    }
+
+    #[test]
+    fn test_next_cycles_through_the_windows() {
+        assert_eq!(RateWindow::Instant.next(), RateWindow::ALL[1]);
+        assert_eq!(RateWindow::ALL[4].next(), RateWindow::Instant);
+    }
+}
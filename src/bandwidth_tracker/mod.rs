@@ -1,3 +1,12 @@
+pub mod alerts;
 pub mod bytes;
 mod history_buffer;
+pub mod interface_counters;
+pub mod measure;
+pub mod proc_source;
+pub mod quotas;
+pub mod recording;
+pub mod sock_diag_source;
+pub mod source;
+pub mod store;
 pub mod tracker;
@@ -0,0 +1,209 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    os::unix::fs::MetadataExt,
+    time::{Duration, Instant, SystemTime},
+};
+
+use procfs::process::Process;
+
+use crate::system::sockets::{socket_inodes, sockets_in_namespace_of};
+
+use super::source::{read_interfaces, CounterSource, Snapshot};
+
+/// Reading the sockets of every process is costly, the counters of the processes are kept between the reads
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Estimates the traffic of the processes without privileges, from the socket tables and the interface counters of `/proc`
+///
+/// The traffic of each network namespace is split between the processes with connections in it, weighted by
+/// their number of connections and the data waiting in their queues. Only the processes whose `/proc/<pid>/fd`
+/// can be read are seen, usually the ones of the same user.
+#[derive(Default)]
+pub struct ProcSource {
+    /// (received, send) bytes attributed to each process so far
+    processes: HashMap<i32, (u64, u64)>,
+    /// Last (received, send) counters of the interfaces of each network namespace, by namespace inode
+    namespaces: HashMap<u64, (u64, u64)>,
+    /// When the sockets of the processes were last read
+    polled_at: Option<Instant>,
+}
+
+/// (received, send) bytes of a namespace since the last poll, and the (pid, received, send) weights of its processes
+type NamespaceTraffic = ((u64, u64), Vec<(i32, u64, u64)>);
+
+impl ProcSource {
+    pub fn new() -> ProcSource {
+        ProcSource::default()
+    }
+
+    /// Traffic of the network namespaces of the processes with sockets, adds the pid of every process to `alive`
+    fn namespace_traffic(
+        &mut self,
+        alive: &mut HashSet<i32>,
+    ) -> Result<Vec<NamespaceTraffic>, Box<dyn Error>> {
+        let mut processes_by_namespace: HashMap<u64, Vec<(Process, HashSet<u64>)>> = HashMap::new();
+        for process in procfs::process::all_processes()?.filter_map(Result::ok) {
+            alive.insert(process.pid);
+            let inodes = socket_inodes(&process);
+            if inodes.is_empty() {
+                continue;
+            }
+            let Ok(namespace) = std::fs::metadata(format!("/proc/{}/ns/net", process.pid)) else {
+                continue;
+            };
+            processes_by_namespace
+                .entry(namespace.ino())
+                .or_default()
+                .push((process, inodes));
+        }
+
+        let mut namespaces = HashMap::new();
+        let traffic = processes_by_namespace
+            .into_iter()
+            .filter_map(|(namespace, processes)| {
+                let counters = namespace_counters(&processes[0].0)?;
+                namespaces.insert(namespace, counters);
+                // the first time a namespace is seen only gives the counters to start from
+                let last = *self.namespaces.get(&namespace)?;
+                let delta = (
+                    counters.0.saturating_sub(last.0),
+                    counters.1.saturating_sub(last.1),
+                );
+
+                let sockets = sockets_in_namespace_of(&processes[0].0);
+                let weights = processes
+                    .iter()
+                    .map(|(process, inodes)| {
+                        sockets
+                            .iter()
+                            .filter(|socket| {
+                                inodes.contains(&socket.inode) && socket.carries_traffic()
+                            })
+                            .fold((process.pid, 0, 0), |(pid, received, send), socket| {
+                                (
+                                    pid,
+                                    received + 1 + u64::from(socket.rx_queue),
+                                    send + 1 + u64::from(socket.tx_queue),
+                                )
+                            })
+                    })
+                    .collect();
+                Some((delta, weights))
+            })
+            .collect();
+        self.namespaces = namespaces;
+        Ok(traffic)
+    }
+
+    /// Adds the shares of the traffic to the processes, the counters are kept until the process exits
+    fn account(&mut self, traffic: Vec<NamespaceTraffic>, alive: &HashSet<i32>) {
+        for ((received, send), weights) in traffic {
+            let received_weights = weights.iter().map(|(pid, weight, _)| (*pid, *weight));
+            for (pid, share) in split(received, received_weights) {
+                self.processes.entry(pid).or_default().0 += share;
+            }
+            let send_weights = weights.iter().map(|(pid, _, weight)| (*pid, *weight));
+            for (pid, share) in split(send, send_weights) {
+                self.processes.entry(pid).or_default().1 += share;
+            }
+        }
+        self.processes.retain(|pid, _| alive.contains(pid));
+    }
+}
+
+/// (received, send) bytes of the interfaces of the namespace of the process, the loopback excluded
+fn namespace_counters(process: &Process) -> Option<(u64, u64)> {
+    let interfaces = process.dev_status().ok()?;
+    Some(
+        interfaces
+            .iter()
+            .filter(|(interface, _)| *interface != "lo")
+            .fold((0, 0), |(received, send), (_, status)| {
+                (received + status.recv_bytes, send + status.sent_bytes)
+            }),
+    )
+}
+
+/// Shares of the `bytes` of each pid, proportional to its weight
+fn split(bytes: u64, weights: impl Iterator<Item = (i32, u64)> + Clone) -> Vec<(i32, u64)> {
+    let total: u64 = weights.clone().map(|(_, weight)| weight).sum();
+    if total == 0 {
+        return Vec::new();
+    }
+    weights
+        .filter(|(_, weight)| *weight > 0)
+        .map(|(pid, weight)| {
+            let share = u128::from(bytes) * u128::from(weight) / u128::from(total);
+            (pid, share as u64)
+        })
+        .collect()
+}
+
+impl CounterSource for ProcSource {
+    fn poll(&mut self) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+        let at = SystemTime::now();
+        // in between, the processes keep their last counters
+        if self
+            .polled_at
+            .is_none_or(|polled_at| polled_at.elapsed() >= POLL_INTERVAL)
+        {
+            self.polled_at = Some(Instant::now());
+            let mut alive = HashSet::new();
+            let traffic = self.namespace_traffic(&mut alive)?;
+            self.account(traffic, &alive);
+        }
+
+        let processes = self
+            .processes
+            .iter()
+            .filter(|(_, bytes)| **bytes != (0, 0))
+            .map(|(pid, (received, send))| (*pid, (*received).into(), (*send).into()))
+            .collect();
+
+        Ok(vec![Snapshot {
+            at,
+            processes,
+            interfaces: read_interfaces()?,
+            connections: Vec::new(),
+            identities: Vec::new(),
+        }])
+    }
+
+    fn is_approximate(&self) -> bool {
+        true
+    }
+
+    fn status(&self) -> Option<String> {
+        Some("approximate: estimated from /proc without the probes".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests_proc_source {
+    use std::collections::HashSet;
+
+    use super::{split, ProcSource};
+
+    #[test]
+    fn test_split_by_weight() {
+        let weights = [(1, 1), (2, 3), (3, 0)];
+        assert_eq!(split(1000, weights.into_iter()), [(1, 250), (2, 750)]);
+        assert_eq!(split(1000, [(1, 0)].into_iter()), []);
+        assert_eq!(split(0, weights.into_iter()), [(1, 0), (2, 0)]);
+    }
+
+    #[test]
+    fn test_counters_are_kept_until_the_process_exits() {
+        let mut source = ProcSource::new();
+        let alive = HashSet::from([1, 2]);
+        source.account(vec![((1000, 100), vec![(1, 1, 1), (2, 1, 1)])], &alive);
+        // the process 2 closed its sockets
+        source.account(vec![((1000, 100), vec![(1, 1, 1)])], &alive);
+        assert_eq!(source.processes[&1], (1500, 150));
+        assert_eq!(source.processes[&2], (500, 50));
+
+        source.account(Vec::new(), &HashSet::from([1]));
+        assert!(!source.processes.contains_key(&2));
+    }
+}
@@ -0,0 +1,476 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    fs, io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::config::{pattern::Pattern, state_dir};
+
+use super::{
+    alerts::Direction,
+    bytes::{parse_amount, NumberOfBytes},
+    measure::TotalsSince,
+    tracker::BandwidthTracker,
+};
+
+/// The usage is written to disk at this interval, and when net-monitor exits
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuotasConfig {
+    /// Where the usage is kept across restarts, defaults to `$XDG_STATE_HOME/net-monitor/usage.toml`
+    pub file: Option<PathBuf>,
+    pub rules: Vec<QuotaRule>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Period {
+    Daily,
+    Monthly,
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Period::Daily => write!(f, "day"),
+            Period::Monthly => write!(f, "month"),
+        }
+    }
+}
+
+/// Budget of data of each program matching `program`, or of the user `user`, over a calendar period
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuotaRule {
+    /// Shown in the warnings, defaults to the position of the rule
+    pub name: Option<String>,
+    pub program: Option<Pattern>,
+    pub user: Option<String>,
+    pub period: Period,
+    #[serde(default)]
+    pub direction: Direction,
+    /// Amount of data like `5GB`
+    #[serde(deserialize_with = "deserialize_bytes")]
+    pub limit: NumberOfBytes,
+}
+
+fn deserialize_bytes<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<NumberOfBytes, D::Error> {
+    parse_amount(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UsageSubject {
+    Program(String),
+    User(String),
+}
+
+impl Display for UsageSubject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UsageSubject::Program(name) => write!(f, "program {name}"),
+            UsageSubject::User(user) => write!(f, "user {user}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counters {
+    pub received: NumberOfBytes,
+    pub send: NumberOfBytes,
+}
+
+impl Counters {
+    pub fn of(&self, direction: Direction) -> NumberOfBytes {
+        match direction {
+            Direction::Rx => self.received,
+            Direction::Tx => self.send,
+            Direction::Total => self.received + self.send,
+        }
+    }
+}
+
+/// Data used by the programs and the users since the start of a period
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeriodUsage {
+    /// First day of the period, `YYYY-MM-DD`
+    pub start: String,
+    pub programs: BTreeMap<String, Counters>,
+    pub users: BTreeMap<String, Counters>,
+}
+
+impl PeriodUsage {
+    fn starting(start: NaiveDate) -> PeriodUsage {
+        PeriodUsage {
+            start: start.format("%Y-%m-%d").to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn of(&self, subject: &UsageSubject) -> Counters {
+        let counters = match subject {
+            UsageSubject::Program(name) => self.programs.get(name),
+            UsageSubject::User(user) => self.users.get(user),
+        };
+        counters.copied().unwrap_or_default()
+    }
+}
+
+/// Content of the usage file
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct UsageFile {
+    daily: PeriodUsage,
+    monthly: PeriodUsage,
+}
+
+/// What is left of a quota for one subject
+pub struct Budget {
+    pub rule: String,
+    pub subject: UsageSubject,
+    pub period: Period,
+    pub used: NumberOfBytes,
+    pub limit: NumberOfBytes,
+}
+
+impl Budget {
+    pub fn remaining(&self) -> NumberOfBytes {
+        (self.limit - self.used).max(NumberOfBytes::default())
+    }
+}
+
+/// A quota used up during the current period
+pub struct QuotaWarning(pub Budget);
+
+impl Display for QuotaWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let budget = &self.0;
+        write!(
+            f,
+            "quota {}: {} used {} of {} this {}",
+            budget.rule, budget.subject, budget.used, budget.limit, budget.period
+        )
+    }
+}
+
+/// Daily and monthly usage of the programs and users, checked against the quotas
+pub struct UsageAccounting {
+    rules: Vec<QuotaRule>,
+    file: Option<PathBuf>,
+    pub daily: PeriodUsage,
+    pub monthly: PeriodUsage,
+    /// (received, send) totals of the processes when they were last accounted
+    accounted: HashMap<i32, (NumberOfBytes, NumberOfBytes)>,
+    /// (rule, subject) already warned about during the current period
+    warned: HashSet<(usize, UsageSubject)>,
+    last_save: Instant,
+}
+
+impl UsageAccounting {
+    pub fn new(config: QuotasConfig) -> Result<UsageAccounting, String> {
+        if let Some(index) = config
+            .rules
+            .iter()
+            .position(|rule| rule.program.is_some() == rule.user.is_some())
+        {
+            return Err(format!(
+                "quota #{} needs either a `program` or a `user`",
+                index + 1
+            ));
+        }
+
+        // the usage is only kept across restarts for the quotas
+        let file = match config.rules.is_empty() {
+            true => None,
+            false => config.file.or_else(default_usage_path),
+        };
+        let usage = match &file {
+            Some(path) if path.exists() => {
+                let content = fs::read_to_string(path)
+                    .map_err(|err| format!("cannot read {}: {err}", path.display()))?;
+                toml::from_str(&content)
+                    .map_err(|err| format!("invalid usage file {}: {err}", path.display()))?
+            }
+            _ => UsageFile::default(),
+        };
+
+        let mut accounting = UsageAccounting::with_usage(config.rules, file, usage);
+        accounting.roll_over(Local::now().date_naive());
+        Ok(accounting)
+    }
+
+    fn with_usage(
+        rules: Vec<QuotaRule>,
+        file: Option<PathBuf>,
+        usage: UsageFile,
+    ) -> UsageAccounting {
+        UsageAccounting {
+            rules,
+            file,
+            daily: usage.daily,
+            monthly: usage.monthly,
+            accounted: HashMap::new(),
+            warned: HashSet::new(),
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Adds the data used by the processes since the last tick, returns the quotas used up by it
+    pub fn account<'a>(
+        &mut self,
+        tracker: &BandwidthTracker,
+        identity_of: impl Fn(i32) -> Option<(&'a str, &'a str)>,
+    ) -> Vec<QuotaWarning> {
+        self.roll_over(Local::now().date_naive());
+
+        let at = tracker.last_tick();
+        self.accounted.retain(|pid, _| tracker.is_tracked(*pid));
+        let pids: Vec<i32> = tracker
+            .get_throughput_at(at, Default::default())
+            .map(|(pid, _, _)| pid)
+            .collect();
+        for pid in pids {
+            // unknown processes are accounted once their identity is read
+            let Some((name, user)) = identity_of(pid) else {
+                continue;
+            };
+            let Some(totals) = tracker.get_nbr_of_bytes_at(pid, at, TotalsSince::Start) else {
+                continue;
+            };
+            let previous = self.accounted.insert(pid, totals).unwrap_or_default();
+            // counters going backward wrapped or were reset, nothing is counted for them
+            let zero = NumberOfBytes::default();
+            let used = Counters {
+                received: (totals.0 - previous.0).max(zero),
+                send: (totals.1 - previous.1).max(zero),
+            };
+            if used != Counters::default() {
+                self.add(name, user, used);
+            }
+        }
+
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            // a failed save is retried at the next interval
+            let _ = self.save();
+        }
+        self.check()
+    }
+
+    fn add(&mut self, name: &str, user: &str, used: Counters) {
+        for usage in [&mut self.daily, &mut self.monthly] {
+            for counters in [
+                usage.programs.entry(name.to_string()).or_default(),
+                usage.users.entry(user.to_string()).or_default(),
+            ] {
+                counters.received = counters.received + used.received;
+                counters.send = counters.send + used.send;
+            }
+        }
+    }
+
+    /// Starts new periods when the day or the month changed
+    fn roll_over(&mut self, today: NaiveDate) {
+        let day = PeriodUsage::starting(today);
+        if self.daily.start != day.start {
+            self.daily = day;
+            self.warned
+                .retain(|(rule, _)| self.rules[*rule].period != Period::Daily);
+        }
+        let month = PeriodUsage::starting(today.with_day0(0).unwrap_or(today));
+        if self.monthly.start != month.start {
+            self.monthly = month;
+            self.warned
+                .retain(|(rule, _)| self.rules[*rule].period != Period::Monthly);
+        }
+    }
+
+    /// Warnings for the quotas used up since the last check
+    fn check(&mut self) -> Vec<QuotaWarning> {
+        let used_up: Vec<_> = self
+            .budgets_by_rule()
+            .filter(|(_, budget)| budget.used >= budget.limit)
+            .collect();
+        used_up
+            .into_iter()
+            .filter(|(index, budget)| self.warned.insert((*index, budget.subject.clone())))
+            .map(|(_, budget)| QuotaWarning(budget))
+            .collect()
+    }
+
+    /// Budget of each subject with a quota
+    pub fn budgets(&self) -> Vec<Budget> {
+        self.budgets_by_rule().map(|(_, budget)| budget).collect()
+    }
+
+    fn budgets_by_rule(&self) -> impl Iterator<Item = (usize, Budget)> + '_ {
+        self.rules
+            .iter()
+            .enumerate()
+            .flat_map(move |(index, rule)| {
+                let usage = match rule.period {
+                    Period::Daily => &self.daily,
+                    Period::Monthly => &self.monthly,
+                };
+                let subjects: Vec<UsageSubject> = match (&rule.program, &rule.user) {
+                    (Some(pattern), _) => usage
+                        .programs
+                        .keys()
+                        .filter(|name| pattern.matches(name))
+                        .map(|name| UsageSubject::Program(name.clone()))
+                        .collect(),
+                    (None, Some(user)) => vec![UsageSubject::User(user.clone())],
+                    (None, None) => Vec::new(),
+                };
+                subjects.into_iter().map(move |subject| {
+                    let budget = Budget {
+                        rule: rule
+                            .name
+                            .clone()
+                            .unwrap_or_else(|| format!("#{}", index + 1)),
+                        used: usage.of(&subject).of(rule.direction),
+                        subject,
+                        period: rule.period,
+                        limit: rule.limit,
+                    };
+                    (index, budget)
+                })
+            })
+    }
+
+    /// Writes the usage to its file, through a temporary file so that a crash cannot corrupt it
+    /// The usage read from the file is still counted on, but it is never written back
+    pub fn keep_in_memory(&mut self) {
+        self.file = None;
+    }
+
+    pub fn save(&mut self) -> io::Result<()> {
+        self.last_save = Instant::now();
+        let Some(path) = &self.file else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let usage = UsageFile {
+            daily: self.daily.clone(),
+            monthly: self.monthly.clone(),
+        };
+        let content = toml::to_string(&usage).map_err(io::Error::other)?;
+        let temporary = path.with_extension("toml.tmp");
+        fs::write(&temporary, content)?;
+        fs::rename(temporary, path)
+    }
+}
+
+/// `$XDG_STATE_HOME/net-monitor/usage.toml`, falling back on `~/.local/state/net-monitor/usage.toml`
+fn default_usage_path() -> Option<PathBuf> {
+    Some(state_dir()?.join("usage.toml"))
+}
+
+#[cfg(test)]
+mod tests_quotas {
+    use std::time::{Duration, SystemTime};
+
+    use chrono::NaiveDate;
+
+    use crate::bandwidth_tracker::{source::Snapshot, tracker::BandwidthTracker};
+
+    use super::{Counters, QuotasConfig, UsageAccounting, UsageSubject};
+
+    fn accounting(rules: &str) -> UsageAccounting {
+        let config: QuotasConfig = toml::from_str(rules).unwrap();
+        let mut accounting = UsageAccounting::with_usage(config.rules, None, Default::default());
+        accounting.roll_over(NaiveDate::from_ymd_opt(2026, 10, 19).unwrap());
+        accounting
+    }
+
+    fn used(received: i32, send: i32) -> Counters {
+        Counters {
+            received: received.into(),
+            send: send.into(),
+        }
+    }
+
+    #[test]
+    fn test_warns_once_per_period() {
+        let mut accounting = accounting(
+            r#"
+            [[rules]]
+            program = "curl"
+            period = "daily"
+            limit = "1KB"
+            "#,
+        );
+
+        accounting.add("curl", "me", used(600, 0));
+        assert!(accounting.check().is_empty());
+        accounting.add("curl", "me", used(0, 600));
+        let warnings = accounting.check();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].0.subject, UsageSubject::Program("curl".into()));
+        accounting.add("curl", "me", used(600, 0));
+        assert!(accounting.check().is_empty());
+
+        accounting.roll_over(NaiveDate::from_ymd_opt(2026, 10, 20).unwrap());
+        accounting.add("curl", "me", used(2000, 0));
+        assert_eq!(accounting.check().len(), 1);
+    }
+
+    #[test]
+    fn test_new_day_keeps_the_month() {
+        let mut accounting = accounting("");
+
+        accounting.add("curl", "me", used(100, 50));
+        accounting.roll_over(NaiveDate::from_ymd_opt(2026, 10, 20).unwrap());
+        accounting.add("curl", "me", used(10, 0));
+
+        let user = UsageSubject::User("me".into());
+        assert_eq!(accounting.daily.of(&user), used(10, 0));
+        assert_eq!(accounting.monthly.of(&user), used(110, 50));
+        assert_eq!(accounting.monthly.start, "2026-10-01");
+    }
+
+    #[test]
+    fn test_wrapped_counters_are_not_subtracted() {
+        let mut accounting = accounting("");
+        let mut tracker = BandwidthTracker::new();
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        for (second, received) in [1000, 3000, 500, 700].into_iter().enumerate() {
+            tracker.ingest(&Snapshot {
+                at: start + Duration::from_secs(second as u64),
+                processes: vec![(42, received.into(), 0.into())],
+                interfaces: Vec::new(),
+                connections: Vec::new(),
+                identities: Vec::new(),
+            });
+            accounting.account(&tracker, |_| Some(("curl", "me")));
+        }
+
+        let program = UsageSubject::Program("curl".into());
+        assert_eq!(accounting.daily.of(&program), used(3200, 0));
+        assert_eq!(accounting.monthly.of(&program), used(3200, 0));
+    }
+
+    #[test]
+    fn test_rule_needs_a_subject() {
+        let config: QuotasConfig = toml::from_str(
+            r#"
+            [[rules]]
+            period = "monthly"
+            limit = "5GB"
+            "#,
+        )
+        .unwrap();
+
+        assert!(UsageAccounting::new(config).is_err());
+    }
+}
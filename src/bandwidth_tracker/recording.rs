@@ -0,0 +1,423 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant, SystemTime},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use procfs::process::Process;
+use serde::{Deserialize, Serialize};
+
+use crate::system::process::ProcessIdentity;
+
+use super::source::{CounterSource, Snapshot};
+
+/// First line of a recording, the snapshots follow one per line
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+    /// The traffic of the processes was estimated
+    #[serde(default)]
+    approximate: bool,
+}
+
+const FORMAT: &str = "net-monitor";
+const VERSION: u32 = 1;
+
+/// The recording is flushed every this many ticks, a crash loses at most the last ones
+const FLUSH_EVERY: usize = 30;
+
+/// Snapshots replayed before the position reached by a seek, to fill the histories
+const SEEK_BACKFILL: Duration = Duration::from_secs(60);
+
+const REPLAY_SPEEDS: [f64; 8] = [0.25, 0.5, 1., 2., 4., 8., 16., 32.];
+
+/// Writes the snapshots of a source to a gzipped file of JSON lines while passing them through
+pub struct Recorder<S> {
+    source: S,
+    writer: GzEncoder<BufWriter<File>>,
+    /// (start time, name) of the processes whose identity is in the recording, a change is recorded again
+    recorded: HashMap<i32, (u64, String)>,
+    ticks: usize,
+}
+
+impl<S: CounterSource> Recorder<S> {
+    pub fn create(path: &Path, source: S) -> Result<Recorder<S>, Box<dyn Error>> {
+        let file =
+            File::create(path).map_err(|err| format!("cannot create {}: {err}", path.display()))?;
+        let mut writer = GzEncoder::new(BufWriter::new(file), Compression::default());
+        let header = Header {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            approximate: source.is_approximate(),
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+        Ok(Recorder {
+            source,
+            writer,
+            recorded: HashMap::new(),
+            ticks: 0,
+        })
+    }
+}
+
+impl<S> Recorder<S> {
+    /// Identity of the process when it is not recorded yet, or when the pid was reused or the process exec'd
+    ///
+    /// Only `/proc/<pid>/stat` is read for the processes already recorded.
+    fn changed_identity(&mut self, pid: i32) -> Option<ProcessIdentity> {
+        let process = Process::new(pid).ok()?;
+        let stat = process.stat().ok()?;
+        let key = (stat.starttime, stat.comm);
+        if self.recorded.get(&pid) == Some(&key) {
+            return None;
+        }
+        let identity = ProcessIdentity::read(&process)?;
+        self.recorded.insert(pid, key);
+        Some(identity)
+    }
+}
+
+impl<S: CounterSource> CounterSource for Recorder<S> {
+    fn poll(&mut self) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+        let mut snapshots = self.source.poll()?;
+        for snapshot in &mut snapshots {
+            // the replay cannot read /proc, the names of the processes are recorded with them
+            for (pid, _, _) in &snapshot.processes {
+                if let Some(identity) = self.changed_identity(*pid) {
+                    snapshot.identities.push((*pid, identity));
+                }
+            }
+            serde_json::to_writer(&mut self.writer, snapshot)?;
+            self.writer.write_all(b"\n")?;
+            self.ticks += 1;
+            if self.ticks.is_multiple_of(FLUSH_EVERY) {
+                self.writer.flush()?;
+            }
+            // identities are only needed by the replay
+            snapshot.identities.clear();
+        }
+        Ok(snapshots)
+    }
+
+    fn is_live(&self) -> bool {
+        self.source.is_live()
+    }
+
+    fn is_approximate(&self) -> bool {
+        self.source.is_approximate()
+    }
+
+    fn status(&self) -> Option<String> {
+        match self.source.status() {
+            Some(status) => Some(format!("recording, {status}")),
+            None => Some("recording".to_string()),
+        }
+    }
+}
+
+/// Plays a recording back at an adjustable speed
+pub struct Replay {
+    snapshots: Vec<Snapshot>,
+    /// Index of the next snapshot to return
+    next: usize,
+    /// Time of the recording reached by the replay
+    position: SystemTime,
+    last_poll: Option<Instant>,
+    speed: usize,
+    paused: bool,
+    approximate: bool,
+    /// Identities recorded before the position reached by a seek, given with the next snapshot
+    seek_identities: Vec<(i32, ProcessIdentity)>,
+}
+
+impl Replay {
+    /// Reads a recording, a truncated end (e.g. after a crash) is ignored
+    pub fn open(path: &Path, speed: f64) -> Result<Replay, Box<dyn Error>> {
+        let file =
+            File::open(path).map_err(|err| format!("cannot open {}: {err}", path.display()))?;
+        let mut lines = BufReader::new(GzDecoder::new(file)).lines();
+
+        let header = lines.next().ok_or("empty recording")??;
+        let header: Header = serde_json::from_str(&header)
+            .map_err(|_| format!("{} is not a recording", path.display()))?;
+        if header.format != FORMAT || header.version != VERSION {
+            return Err(format!(
+                "unsupported recording {} version {}",
+                header.format, header.version
+            )
+            .into());
+        }
+
+        let snapshots: Vec<Snapshot> = lines
+            .map_while(|line| serde_json::from_str(&line.ok()?).ok())
+            .collect();
+        let mut replay = Replay::new(snapshots, speed)?;
+        replay.approximate = header.approximate;
+        Ok(replay)
+    }
+
+    fn new(snapshots: Vec<Snapshot>, speed: f64) -> Result<Replay, Box<dyn Error>> {
+        let start = snapshots.first().ok_or("the recording has no tick")?.at;
+        let speed = REPLAY_SPEEDS
+            .iter()
+            .position(|candidate| *candidate >= speed)
+            .unwrap_or(REPLAY_SPEEDS.len() - 1);
+        Ok(Replay {
+            snapshots,
+            next: 0,
+            position: start,
+            last_poll: None,
+            speed,
+            paused: false,
+            approximate: false,
+            seek_identities: Vec::new(),
+        })
+    }
+
+    fn start(&self) -> SystemTime {
+        self.snapshots[0].at
+    }
+
+    fn end(&self) -> SystemTime {
+        self.snapshots[self.snapshots.len() - 1].at
+    }
+
+    /// Moves the position by a duration of the recording, returns the snapshots reached
+    fn advance(&mut self, elapsed: Duration) -> Vec<Snapshot> {
+        self.position = (self.position + elapsed).min(self.end());
+        let reached = self.snapshots[self.next..]
+            .iter()
+            .take_while(|snapshot| snapshot.at <= self.position)
+            .count();
+        let mut snapshots = self.snapshots[self.next..self.next + reached].to_vec();
+        self.next += reached;
+        if let Some(first) = snapshots.first_mut() {
+            let mut identities = std::mem::take(&mut self.seek_identities);
+            identities.append(&mut first.identities);
+            first.identities = identities;
+        }
+        snapshots
+    }
+}
+
+impl CounterSource for Replay {
+    fn poll(&mut self) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+        let now = Instant::now();
+        let elapsed = match (self.paused, self.last_poll) {
+            (false, Some(last_poll)) => now.duration_since(last_poll),
+            _ => Duration::ZERO,
+        };
+        self.last_poll = Some(now);
+        Ok(self.advance(elapsed.mul_f64(REPLAY_SPEEDS[self.speed])))
+    }
+
+    fn is_live(&self) -> bool {
+        false
+    }
+
+    fn is_approximate(&self) -> bool {
+        self.approximate
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    fn change_speed(&mut self, faster: bool) {
+        self.speed = match faster {
+            true => (self.speed + 1).min(REPLAY_SPEEDS.len() - 1),
+            false => self.speed.saturating_sub(1),
+        };
+    }
+
+    fn seek(&mut self, seconds: i64) -> bool {
+        let offset = Duration::from_secs(seconds.unsigned_abs());
+        let target = if seconds < 0 {
+            self.position
+                .checked_sub(offset)
+                .unwrap_or(self.start())
+                .max(self.start())
+        } else {
+            (self.position + offset).min(self.end())
+        };
+
+        // the backfill is returned at the next poll, along with the snapshots up to the target
+        let backfill_from = target.checked_sub(SEEK_BACKFILL).unwrap_or(target);
+        self.next = self
+            .snapshots
+            .iter()
+            .position(|snapshot| snapshot.at >= backfill_from)
+            .unwrap_or(self.snapshots.len() - 1);
+        self.position = target;
+        self.seek_identities = latest_identities(&self.snapshots[..self.next]);
+        true
+    }
+
+    fn status(&self) -> Option<String> {
+        let position = self
+            .position
+            .duration_since(self.start())
+            .unwrap_or_default();
+        let length = self.end().duration_since(self.start()).unwrap_or_default();
+        let state = if self.position >= self.end() {
+            ", ended"
+        } else if self.paused {
+            ", paused"
+        } else {
+            ""
+        };
+        Some(format!(
+            "replay {} / {} at x{}{state}",
+            minutes_and_seconds(position),
+            minutes_and_seconds(length),
+            REPLAY_SPEEDS[self.speed]
+        ))
+    }
+}
+
+/// Last identity recorded for each pid in the snapshots
+fn latest_identities(snapshots: &[Snapshot]) -> Vec<(i32, ProcessIdentity)> {
+    let identities: HashMap<_, _> = snapshots
+        .iter()
+        .flat_map(|snapshot| snapshot.identities.iter().cloned())
+        .collect();
+    identities.into_iter().collect()
+}
+
+fn minutes_and_seconds(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests_recording {
+    use std::{
+        error::Error,
+        time::{Duration, SystemTime},
+    };
+
+    use crate::{
+        bandwidth_tracker::source::{CounterSource, Snapshot},
+        system::process::ProcessIdentity,
+    };
+
+    use super::{Recorder, Replay};
+
+    fn identity(name: &str) -> ProcessIdentity {
+        ProcessIdentity {
+            name: name.to_string(),
+            cmdline: String::new(),
+            user: "root".to_string(),
+            cgroup: String::new(),
+        }
+    }
+
+    /// One snapshot per second, each pid sending a kilobyte per second
+    fn snapshots(seconds: u64) -> Vec<Snapshot> {
+        (0..seconds)
+            .map(|second| Snapshot {
+                at: SystemTime::UNIX_EPOCH + Duration::from_secs(second),
+                processes: vec![(i32::MAX, 0.into(), (second * 1000).into())],
+                interfaces: Vec::new(),
+                connections: Vec::new(),
+                identities: Vec::new(),
+            })
+            .collect()
+    }
+
+    struct Fixture(Vec<Snapshot>);
+
+    impl CounterSource for Fixture {
+        fn poll(&mut self) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+            Ok(std::mem::take(&mut self.0))
+        }
+    }
+
+    #[test]
+    fn test_replay_a_recording() {
+        let path = std::env::temp_dir().join(format!("net-monitor-{}.rec", std::process::id()));
+        let mut recorder = Recorder::create(&path, Fixture(snapshots(5))).unwrap();
+        assert_eq!(recorder.poll().unwrap().len(), 5);
+        drop(recorder);
+
+        let mut replay = Replay::open(&path, 1.).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.poll().unwrap().len(), 1);
+        let snapshots = replay.advance(Duration::from_millis(2500));
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(
+            snapshots[1].at,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(2)
+        );
+        assert_eq!(replay.status().unwrap(), "replay 0:02 / 0:04 at x1");
+    }
+
+    #[test]
+    fn test_seek_backfills_the_histories() {
+        let mut replay = Replay::new(snapshots(200), 1.).unwrap();
+        replay.advance(Duration::from_secs(10));
+
+        assert!(replay.seek(100));
+        let snapshots = replay.advance(Duration::ZERO);
+        assert_eq!(snapshots.len(), 61);
+        assert_eq!(
+            snapshots[60].at,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(110)
+        );
+
+        replay.seek(-1000);
+        assert_eq!(replay.advance(Duration::ZERO).len(), 1);
+    }
+
+    #[test]
+    fn test_seek_gives_the_identities_recorded_before() {
+        let mut snapshots = snapshots(200);
+        snapshots[5].identities.push((i32::MAX, identity("curl")));
+        // the pid is reused
+        snapshots[20].identities.push((i32::MAX, identity("wget")));
+        let mut replay = Replay::new(snapshots, 1.).unwrap();
+
+        assert!(replay.seek(100));
+        let snapshots = replay.advance(Duration::ZERO);
+        assert_eq!(snapshots[0].identities.len(), 1);
+        assert_eq!(snapshots[0].identities[0].1.name, "wget");
+        assert!(snapshots[1].identities.is_empty());
+    }
+
+    #[test]
+    fn test_recorder_records_changed_identities() {
+        let path = std::env::temp_dir().join(format!("net-monitor-{}-ids.rec", std::process::id()));
+        let pid = std::process::id() as i32;
+        let mut ticks = snapshots(3);
+        ticks
+            .iter_mut()
+            .for_each(|snapshot| snapshot.processes[0].0 = pid);
+        let mut recorder = Recorder::create(&path, Fixture(ticks)).unwrap();
+        recorder.poll().unwrap();
+        // as if the pid had been reused since
+        recorder.recorded.get_mut(&pid).unwrap().0 += 1;
+        recorder.source.0 = snapshots(2);
+        recorder
+            .source
+            .0
+            .iter_mut()
+            .for_each(|snapshot| snapshot.processes[0].0 = pid);
+        recorder.poll().unwrap();
+        drop(recorder);
+
+        let replay = Replay::open(&path, 1.).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let recorded: Vec<_> = replay
+            .snapshots
+            .iter()
+            .map(|snapshot| snapshot.identities.len())
+            .collect();
+        assert_eq!(recorded, [1, 0, 0, 1, 0]);
+    }
+}
@@ -0,0 +1,206 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::system::{
+    sock_diag::{tcp_sockets, TcpCounters},
+    sockets::socket_inodes,
+};
+
+use super::source::{read_interfaces, CounterSource, Snapshot};
+
+/// Reading the fds of every process is costly, the owners of the sockets are read at most at this interval,
+/// unless a new socket has traffic. The dump itself is cheap, it is done on every tick to miss few closed sockets.
+const OWNERS_REFRESH: Duration = Duration::from_secs(1);
+
+/// Counts the TCP bytes of each socket from the kernel with netlink `sock_diag`, without privileges
+///
+/// The counters are exact, but only the TCP sockets of this network namespace are seen, and the bytes of a
+/// socket closed between two polls since the previous one are lost. The sockets are given to the processes
+/// holding them through the inodes of `/proc/<pid>/fd`, usually readable for the processes of the same user.
+pub struct SockDiagSource {
+    /// Last (received, acked) counters of each socket, by inode
+    sockets: HashMap<u64, (u64, u64)>,
+    /// (received, send) bytes attributed to each process so far
+    processes: HashMap<i32, (u64, u64)>,
+    /// Process holding each socket, by inode
+    owners: HashMap<u64, i32>,
+    /// Sockets with traffic but no owner when the owners were read, of the processes whose fds are not readable
+    unowned: HashSet<u64>,
+    /// When the owners were read
+    owners_read_at: Option<Instant>,
+}
+
+impl SockDiagSource {
+    /// Fails when `sock_diag` is not available, the sockets opened before are only counted from now on
+    pub fn open() -> io::Result<SockDiagSource> {
+        Ok(SockDiagSource {
+            sockets: counters_by_inode(&tcp_sockets()?),
+            processes: HashMap::new(),
+            owners: HashMap::new(),
+            unowned: HashSet::new(),
+            owners_read_at: None,
+        })
+    }
+
+    /// Whether the owners are too old, or do not know a socket with traffic
+    fn owners_are_stale(&self, traffic: &HashMap<u64, (u64, u64)>) -> bool {
+        self.owners_read_at
+            .is_none_or(|read_at| read_at.elapsed() >= OWNERS_REFRESH)
+            || traffic
+                .keys()
+                .any(|inode| !self.owners.contains_key(inode) && !self.unowned.contains(inode))
+    }
+
+    /// Reads the sockets of every process, the processes that exited lose their counters
+    fn read_owners(&mut self, traffic: &HashMap<u64, (u64, u64)>) -> Result<(), Box<dyn Error>> {
+        let mut owners = HashMap::new();
+        let mut alive = HashSet::new();
+        for process in procfs::process::all_processes()?.filter_map(Result::ok) {
+            alive.insert(process.pid);
+            // a socket shared after a fork is given to the first of its processes
+            for inode in socket_inodes(&process) {
+                owners.entry(inode).or_insert(process.pid);
+            }
+        }
+        self.unowned = traffic
+            .keys()
+            .filter(|inode| !owners.contains_key(inode))
+            .copied()
+            .collect();
+        self.owners = owners;
+        self.owners_read_at = Some(Instant::now());
+        self.processes.retain(|pid, _| alive.contains(pid));
+        Ok(())
+    }
+
+    /// (received, send) bytes of each socket since the last poll
+    fn socket_traffic(&mut self, sockets: &[TcpCounters]) -> HashMap<u64, (u64, u64)> {
+        let counters = counters_by_inode(sockets);
+        let traffic = counters
+            .iter()
+            .map(|(inode, (received, acked))| {
+                // the sockets opened since the last poll are counted from their start
+                let (last_received, last_acked) =
+                    self.sockets.get(inode).copied().unwrap_or_default();
+                (
+                    *inode,
+                    (
+                        received.saturating_sub(last_received),
+                        acked.saturating_sub(last_acked),
+                    ),
+                )
+            })
+            .filter(|(_, bytes)| *bytes != (0, 0))
+            .collect();
+        self.sockets = counters;
+        traffic
+    }
+}
+
+/// The time-wait and orphan sockets have no inode, they belong to no process anymore
+fn counters_by_inode(sockets: &[TcpCounters]) -> HashMap<u64, (u64, u64)> {
+    sockets
+        .iter()
+        .filter(|socket| socket.inode != 0)
+        .map(|socket| (socket.inode, (socket.bytes_received, socket.bytes_acked)))
+        .collect()
+}
+
+impl CounterSource for SockDiagSource {
+    fn poll(&mut self) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+        let at = SystemTime::now();
+        let sockets = tcp_sockets()?;
+        let traffic = self.socket_traffic(&sockets);
+        if self.owners_are_stale(&traffic) {
+            self.read_owners(&traffic)?;
+        }
+        for (inode, (received, send)) in traffic {
+            if let Some(pid) = self.owners.get(&inode) {
+                let bytes = self.processes.entry(*pid).or_default();
+                bytes.0 += received;
+                bytes.1 += send;
+            }
+        }
+
+        let processes = self
+            .processes
+            .iter()
+            .map(|(pid, (received, send))| (*pid, (*received).into(), (*send).into()))
+            .collect();
+        let connections = self
+            .sockets
+            .iter()
+            .map(|(inode, (received, acked))| (*inode, (*received).into(), (*acked).into()))
+            .collect();
+
+        Ok(vec![Snapshot {
+            at,
+            processes,
+            interfaces: read_interfaces()?,
+            connections,
+            identities: Vec::new(),
+        }])
+    }
+
+    fn status(&self) -> Option<String> {
+        Some("TCP only, from sock_diag without the probes".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests_sock_diag_source {
+    use std::{collections::HashMap, time::Instant};
+
+    use crate::system::sock_diag::TcpCounters;
+
+    use super::{SockDiagSource, OWNERS_REFRESH};
+
+    fn socket(inode: u64, bytes_received: u64, bytes_acked: u64) -> TcpCounters {
+        TcpCounters {
+            inode,
+            uid: 0,
+            bytes_received,
+            bytes_acked,
+        }
+    }
+
+    #[test]
+    fn test_socket_traffic_since_last_poll() {
+        let mut source = SockDiagSource {
+            sockets: [(1, (100, 200))].into(),
+            processes: Default::default(),
+            owners: Default::default(),
+            unowned: Default::default(),
+            owners_read_at: None,
+        };
+        let traffic = source.socket_traffic(&[
+            socket(1, 150, 200),
+            socket(2, 10, 20),
+            socket(0, 1000, 1000),
+        ]);
+        assert_eq!(traffic, [(1, (50, 0)), (2, (10, 20))].into());
+
+        let traffic = source.socket_traffic(&[socket(2, 10, 25)]);
+        assert_eq!(traffic, [(2, (0, 5))].into());
+    }
+
+    #[test]
+    fn test_owners_are_read_again_for_new_sockets() {
+        let mut source = SockDiagSource {
+            sockets: Default::default(),
+            processes: Default::default(),
+            owners: [(1, 10)].into(),
+            unowned: [2].into(),
+            owners_read_at: Some(Instant::now()),
+        };
+        assert!(!source.owners_are_stale(&[(1, (5, 5)), (2, (5, 5))].into()));
+        assert!(source.owners_are_stale(&[(3, (5, 5))].into()));
+
+        source.owners_read_at = Some(Instant::now() - OWNERS_REFRESH);
+        assert!(source.owners_are_stale(&HashMap::new()));
+    }
+}
@@ -0,0 +1,145 @@
+use std::{
+    error::Error,
+    time::{Duration, SystemTime},
+};
+
+use clap::ValueEnum;
+use libbpf_rs::{Map, MapCore, MapFlags};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::system::process::ProcessIdentity;
+
+use super::{bytes::NumberOfBytes, interface_counters::InterfaceCounters};
+
+/// (interface, received, send) bytes and the packet counters of an interface
+pub type InterfaceTraffic = (String, NumberOfBytes, NumberOfBytes, InterfaceCounters);
+
+/// Counters of every process and interface at one tick
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    #[serde(with = "epoch_millis")]
+    pub at: SystemTime,
+    /// (pid, received, send) bytes seen by the probes since they were loaded
+    pub processes: Vec<(i32, NumberOfBytes, NumberOfBytes)>,
+    /// Counters of the interfaces, from `/proc/net/dev`
+    pub interfaces: Vec<InterfaceTraffic>,
+    /// (socket inode, received, send) bytes of the TCP connections, only known with `sock_diag`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connections: Vec<(u64, NumberOfBytes, NumberOfBytes)>,
+    /// Identities of the processes seen for the first time, only filled in the recordings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identities: Vec<(i32, ProcessIdentity)>,
+}
+
+/// Where the live counters come from, chosen with `--source`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SourceKind {
+    /// eBPF probes, every process and protocol, root or `CAP_BPF` is needed
+    Probes,
+    /// TCP byte counters of each socket from netlink `sock_diag`, no privileges are needed
+    SockDiag,
+    /// Estimate from the socket tables and interface counters of `/proc`, no privileges are needed
+    Proc,
+}
+
+/// Where the `BandwidthTracker` gets its counters from: the probes, or a recording
+pub trait CounterSource {
+    /// Snapshots of the ticks since the last poll, the oldest first
+    fn poll(&mut self) -> Result<Vec<Snapshot>, Box<dyn Error>>;
+
+    /// Live sources describe the processes running on this host, `/proc` can be read for them
+    fn is_live(&self) -> bool {
+        true
+    }
+
+    /// Sources estimating the traffic of the processes instead of measuring it
+    fn is_approximate(&self) -> bool {
+        false
+    }
+
+    fn set_paused(&mut self, _paused: bool) {}
+
+    /// Replays faster or slower
+    fn change_speed(&mut self, _faster: bool) {}
+
+    /// Moves in a recording by a number of seconds, returns whether the histories must be rebuilt
+    fn seek(&mut self, _seconds: i64) -> bool {
+        false
+    }
+
+    /// Shown in the status line, like the position in a recording
+    fn status(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Counters of the eBPF probes and of `/proc/net/dev`
+pub struct ProbeSource<'a> {
+    packet_stats: &'a Map<'a>,
+}
+
+impl<'a> ProbeSource<'a> {
+    pub fn new(packet_stats: &'a Map<'a>) -> ProbeSource<'a> {
+        ProbeSource { packet_stats }
+    }
+}
+
+impl CounterSource for ProbeSource<'_> {
+    fn poll(&mut self) -> Result<Vec<Snapshot>, Box<dyn Error>> {
+        let at = SystemTime::now();
+        let processes = self
+            .packet_stats
+            .keys()
+            .filter_map(|key| {
+                let value = self.packet_stats.lookup(&key, MapFlags::ANY).ok()??;
+                // the probes count in u32, they wrap after 4 GiB
+                let bytes_received = u32::from_ne_bytes(value[..4].try_into().ok()?);
+                let bytes_send = u32::from_ne_bytes(value[4..].try_into().ok()?);
+                let pid = i32::from_ne_bytes(key.try_into().ok()?);
+                Some((pid, bytes_received.into(), bytes_send.into()))
+            })
+            .collect();
+
+        Ok(vec![Snapshot {
+            at,
+            processes,
+            interfaces: read_interfaces()?,
+            connections: Vec::new(),
+            identities: Vec::new(),
+        }])
+    }
+}
+
+/// Counters of the interfaces of this network namespace, from `/proc/net/dev`
+pub fn read_interfaces() -> procfs::ProcResult<Vec<InterfaceTraffic>> {
+    let interfaces = procfs::net::dev_status()?
+        .into_iter()
+        .map(|(interface, status)| {
+            (
+                interface,
+                status.recv_bytes.into(),
+                status.sent_bytes.into(),
+                (&status).into(),
+            )
+        })
+        .collect();
+    Ok(interfaces)
+}
+
+/// Times are written as milliseconds since the epoch
+mod epoch_millis {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(at: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        let millis = at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        serializer.serialize_u64(millis)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        let millis = u64::deserialize(deserializer)?;
+        Ok(SystemTime::UNIX_EPOCH + Duration::from_millis(millis))
+    }
+}
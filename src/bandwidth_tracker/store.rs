@@ -0,0 +1,498 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+use rusqlite::{params, Connection};
+use serde::Deserialize;
+
+use crate::config::state_dir;
+
+use super::{
+    bytes::{BytesPerSecond, NumberOfBytes},
+    tracker::BandwidthTracker,
+};
+
+/// (time, received, send) points of a chart
+pub type RateHistory = Vec<(SystemTime, BytesPerSecond, BytesPerSecond)>;
+
+/// Seconds per bucket and retention of each tier, the finest first
+const TIERS: [(u64, Duration); 3] = [
+    (1, Duration::from_secs(5 * 60)),
+    (10, Duration::from_secs(24 * 60 * 60)),
+    (60, Duration::from_secs(31 * 24 * 60 * 60)),
+];
+
+/// The buckets are written in one transaction at this interval
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Expired buckets are deleted at this interval
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Points of a zoomed chart, the buckets are merged to stay around this number
+const CHART_POINTS: u64 = 600;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS usage (
+    resolution INTEGER NOT NULL,
+    bucket INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    name TEXT NOT NULL,
+    received INTEGER NOT NULL,
+    send INTEGER NOT NULL,
+    PRIMARY KEY (resolution, kind, bucket, name)
+) WITHOUT ROWID;
+";
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HistoryConfig {
+    /// Keep the long-term history, on by default
+    pub enabled: bool,
+    /// Defaults to `$XDG_STATE_HOME/net-monitor/history.sqlite`
+    pub file: Option<PathBuf>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            enabled: true,
+            file: None,
+        }
+    }
+}
+
+impl HistoryConfig {
+    pub fn path(&self) -> Option<PathBuf> {
+        self.file
+            .clone()
+            .or_else(|| Some(state_dir()?.join("history.sqlite")))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Program,
+    Interface,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Program => "program",
+            Kind::Interface => "interface",
+        }
+    }
+}
+
+/// Time span of the interfaces chart, the live one comes from the tracker and the others from the store
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChartZoom {
+    #[default]
+    Live,
+    FiveMinutes,
+    Hour,
+    Day,
+    Month,
+}
+
+impl ChartZoom {
+    const ALL: [ChartZoom; 5] = [
+        ChartZoom::Live,
+        ChartZoom::FiveMinutes,
+        ChartZoom::Hour,
+        ChartZoom::Day,
+        ChartZoom::Month,
+    ];
+
+    /// `None` for the live chart
+    pub fn span(self) -> Option<Duration> {
+        let minutes = match self {
+            ChartZoom::Live => return None,
+            ChartZoom::FiveMinutes => 5,
+            ChartZoom::Hour => 60,
+            ChartZoom::Day => 24 * 60,
+            ChartZoom::Month => 30 * 24 * 60,
+        };
+        Some(Duration::from_secs(minutes * 60))
+    }
+
+    /// The next longer span, or shorter one when zooming in
+    pub fn zoom(self, zoom_out: bool) -> ChartZoom {
+        let index = Self::ALL.iter().position(|zoom| *zoom == self).unwrap_or(0);
+        match zoom_out {
+            true => Self::ALL[(index + 1).min(Self::ALL.len() - 1)],
+            false => Self::ALL[index.saturating_sub(1)],
+        }
+    }
+}
+
+impl Display for ChartZoom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChartZoom::Live => write!(f, "live"),
+            ChartZoom::FiveMinutes => write!(f, "last 5 minutes"),
+            ChartZoom::Hour => write!(f, "last hour"),
+            ChartZoom::Day => write!(f, "last day"),
+            ChartZoom::Month => write!(f, "last 30 days"),
+        }
+    }
+}
+
+/// Bytes exchanged by the programs and interfaces, downsampled in tiers kept in an SQLite file
+pub struct HistoryStore {
+    connection: Connection,
+    /// (received, send) bytes not written yet by (resolution, bucket, kind, name)
+    pending: HashMap<(u64, u64, Kind, String), (i64, i64)>,
+    last_flush: Instant,
+    last_prune: Option<Instant>,
+}
+
+impl HistoryStore {
+    pub fn open(config: &HistoryConfig) -> Result<Option<HistoryStore>, String> {
+        let Some(path) = config.path().filter(|_| config.enabled) else {
+            return Ok(None);
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|err| format!("cannot create {}: {err}", dir.display()))?;
+        }
+        Connection::open(&path)
+            .and_then(HistoryStore::with_connection)
+            .map(Some)
+            .map_err(|err| format!("cannot open the history {}: {err}", path.display()))
+    }
+
+    fn with_connection(connection: Connection) -> rusqlite::Result<HistoryStore> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(HistoryStore {
+            connection,
+            pending: HashMap::new(),
+            last_flush: Instant::now(),
+            last_prune: None,
+        })
+    }
+
+    /// Adds the bytes of the last tick to the buckets, and writes them from time to time
+    pub fn record<'a>(
+        &mut self,
+        tracker: &BandwidthTracker,
+        name_of: impl Fn(i32) -> Option<&'a str>,
+    ) -> rusqlite::Result<()> {
+        let at = tracker.last_tick();
+        for (pid, received, send) in tracker.get_bytes_of_last_tick_per_pid() {
+            if let Some(name) = name_of(pid) {
+                self.add(at, Kind::Program, name, received, send);
+            }
+        }
+        for (interface, received, send) in tracker.get_bytes_of_last_tick_per_interface() {
+            self.add(at, Kind::Interface, interface, received, send);
+        }
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+        if self
+            .last_prune
+            .is_none_or(|last| last.elapsed() >= PRUNE_INTERVAL)
+        {
+            self.prune(SystemTime::now())?;
+        }
+        Ok(())
+    }
+
+    fn add(
+        &mut self,
+        at: SystemTime,
+        kind: Kind,
+        name: &str,
+        received: NumberOfBytes,
+        send: NumberOfBytes,
+    ) {
+        if received == NumberOfBytes::default() && send == NumberOfBytes::default() {
+            return;
+        }
+        let seconds = epoch_seconds(at);
+        for (resolution, _) in TIERS {
+            let bucket = seconds - seconds % resolution;
+            let bytes = self
+                .pending
+                .entry((resolution, bucket, kind, name.to_string()))
+                .or_default();
+            bytes.0 += i64::from(received);
+            bytes.1 += i64::from(send);
+        }
+    }
+
+    pub fn flush(&mut self) -> rusqlite::Result<()> {
+        self.last_flush = Instant::now();
+        let transaction = self.connection.transaction()?;
+        {
+            let mut upsert = transaction.prepare_cached(
+                "INSERT INTO usage (resolution, bucket, kind, name, received, send)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (resolution, kind, bucket, name) DO UPDATE
+                 SET received = received + excluded.received, send = send + excluded.send",
+            )?;
+            for ((resolution, bucket, kind, name), (received, send)) in self.pending.drain() {
+                upsert.execute(params![
+                    resolution,
+                    bucket,
+                    kind.as_str(),
+                    name,
+                    received,
+                    send
+                ])?;
+            }
+        }
+        transaction.commit()
+    }
+
+    fn prune(&mut self, now: SystemTime) -> rusqlite::Result<()> {
+        self.last_prune = Some(Instant::now());
+        for (resolution, retention) in TIERS {
+            self.connection.execute(
+                "DELETE FROM usage WHERE resolution = ?1 AND bucket < ?2",
+                params![resolution, epoch_seconds(now - retention)],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// (time, received, send) rates of each interface over the `span` before `now`
+    pub fn interface_rates(
+        &self,
+        span: Duration,
+        now: SystemTime,
+    ) -> rusqlite::Result<BTreeMap<String, RateHistory>> {
+        let from = now - span;
+        let resolution = resolution_covering(from, now);
+        let step = (span.as_secs() / CHART_POINTS)
+            .max(resolution)
+            .next_multiple_of(resolution);
+
+        let mut query = self.connection.prepare_cached(
+            "SELECT bucket / ?1 * ?1 AS point, name, SUM(received), SUM(send) FROM usage
+             WHERE resolution = ?2 AND kind = 'interface' AND bucket >= ?3
+             GROUP BY point, name ORDER BY point",
+        )?;
+        let rows = query.query_map(params![step, resolution, epoch_seconds(from)], |row| {
+            Ok((
+                row.get::<_, u64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?;
+
+        let mut bytes: BTreeMap<_, HashMap<_, _>> = BTreeMap::new();
+        for row in rows {
+            let (point, name, received, send) = row?;
+            bytes
+                .entry(name)
+                .or_default()
+                .insert(point, (received, send));
+        }
+
+        // the steps without traffic are drawn at zero rather than joined by a line
+        let step_duration = Duration::from_secs(step);
+        let first = epoch_seconds(from) / step * step;
+        let last = epoch_seconds(now) / step * step;
+        let rates = bytes
+            .into_iter()
+            .map(|(name, bytes)| {
+                let points = (first..=last)
+                    .step_by(step as usize)
+                    .map(|point| {
+                        let (received, send) = bytes.get(&point).copied().unwrap_or_default();
+                        let rate = |bytes: i64| BytesPerSecond::new(bytes.into(), step_duration);
+                        // a point is drawn at the end of its bucket
+                        let at =
+                            SystemTime::UNIX_EPOCH + Duration::from_secs(point) + step_duration;
+                        (at.min(now), rate(received), rate(send))
+                    })
+                    .collect();
+                (name, points)
+            })
+            .collect();
+        Ok(rates)
+    }
+
+    /// (name, received, send) bytes of the programs or interfaces between `from` and `to`, the most used first
+    pub fn top(
+        &self,
+        kind: Kind,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> rusqlite::Result<Vec<(String, NumberOfBytes, NumberOfBytes)>> {
+        let resolution = resolution_covering(from, SystemTime::now());
+        let mut query = self.connection.prepare_cached(
+            "SELECT name, SUM(received), SUM(send) FROM usage
+             WHERE resolution = ?1 AND kind = ?2 AND bucket >= ?3 AND bucket < ?4
+             GROUP BY name ORDER BY SUM(received) + SUM(send) DESC",
+        )?;
+        let rows = query.query_map(
+            params![
+                resolution,
+                kind.as_str(),
+                // the buckets overlapping the start are included
+                epoch_seconds(from) - epoch_seconds(from) % resolution,
+                epoch_seconds(to)
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?.into(),
+                    row.get::<_, i64>(2)?.into(),
+                ))
+            },
+        )?;
+        rows.collect()
+    }
+}
+
+impl Drop for HistoryStore {
+    fn drop(&mut self) {
+        // nothing can be done about a failure when exiting
+        let _ = self.flush();
+    }
+}
+
+/// The finest tier still holding the buckets since `from`
+fn resolution_covering(from: SystemTime, now: SystemTime) -> u64 {
+    let age = now.duration_since(from).unwrap_or_default();
+    TIERS
+        .iter()
+        .find(|(_, retention)| age <= *retention)
+        .unwrap_or(&TIERS[TIERS.len() - 1])
+        .0
+}
+
+fn epoch_seconds(at: SystemTime) -> u64 {
+    at.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests_store {
+    use std::time::{Duration, SystemTime};
+
+    use rusqlite::Connection;
+
+    use super::{ChartZoom, HistoryStore, Kind};
+
+    fn store() -> HistoryStore {
+        HistoryStore::with_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_top_programs() {
+        let mut store = store();
+        let now = SystemTime::now();
+        store.add(
+            now - Duration::from_secs(30),
+            Kind::Program,
+            "curl",
+            100.into(),
+            0.into(),
+        );
+        store.add(
+            now - Duration::from_secs(20),
+            Kind::Program,
+            "ssh",
+            10.into(),
+            20.into(),
+        );
+        store.add(
+            now - Duration::from_secs(10),
+            Kind::Program,
+            "curl",
+            50.into(),
+            0.into(),
+        );
+        store.flush().unwrap();
+
+        let top = store
+            .top(Kind::Program, now - Duration::from_secs(60), now)
+            .unwrap();
+        assert_eq!(
+            top,
+            vec![
+                ("curl".to_string(), 150.into(), 0.into()),
+                ("ssh".to_string(), 10.into(), 20.into())
+            ]
+        );
+        assert!(store
+            .top(Kind::Interface, now - Duration::from_secs(60), now)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_old_buckets_only_stay_in_the_coarse_tiers() {
+        let mut store = store();
+        let now = SystemTime::now();
+        let two_hours_ago = now - Duration::from_secs(2 * 60 * 60);
+        store.add(
+            two_hours_ago,
+            Kind::Interface,
+            "eth0",
+            6000.into(),
+            0.into(),
+        );
+        store.flush().unwrap();
+        store.prune(now).unwrap();
+
+        let rates = store
+            .interface_rates(ChartZoom::Day.span().unwrap(), now)
+            .unwrap();
+        let with_traffic = rates["eth0"]
+            .iter()
+            .filter(|(_, received, _)| f64::from(*received) > 0.)
+            .count();
+        assert_eq!(with_traffic, 1);
+        let in_five_minutes = store
+            .top(Kind::Interface, now - Duration::from_secs(300), now)
+            .unwrap();
+        assert!(in_five_minutes.is_empty());
+    }
+
+    #[test]
+    fn test_missing_steps_are_zero() {
+        let mut store = store();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        for seconds_ago in [50, 10] {
+            store.add(
+                now - Duration::from_secs(seconds_ago),
+                Kind::Interface,
+                "eth0",
+                100.into(),
+                0.into(),
+            );
+        }
+        store.flush().unwrap();
+
+        let rates = store
+            .interface_rates(ChartZoom::FiveMinutes.span().unwrap(), now)
+            .unwrap();
+        let received: Vec<_> = rates["eth0"]
+            .iter()
+            .map(|(_, received, _)| f64::from(*received))
+            .collect();
+        assert_eq!(received.len(), 301);
+        assert_eq!(received.iter().filter(|rate| **rate > 0.).count(), 2);
+        assert_eq!(received[250], 100.);
+        assert_eq!(received[251], 0.);
+        assert_eq!(received[290], 100.);
+    }
+
+    #[test]
+    fn test_zoom_stops_at_the_ends() {
+        assert_eq!(ChartZoom::Live.zoom(false), ChartZoom::Live);
+        assert_eq!(ChartZoom::Live.zoom(true), ChartZoom::FiveMinutes);
+        assert_eq!(ChartZoom::Month.zoom(true), ChartZoom::Month);
+    }
+}
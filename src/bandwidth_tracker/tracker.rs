@@ -1,95 +1,212 @@
 use std::time::SystemTime;
 use std::{collections::HashMap, time::Duration};
 
-use libbpf_rs::{Map, MapCore, MapFlags};
-
 use super::bytes::{BytesPerSecond, NumberOfBytes};
 use super::history_buffer::HistoryBuffer;
+use super::interface_counters::{InterfaceCounters, InterfaceRates};
+use super::measure::{RateWindow, TotalsSince};
+use super::source::Snapshot;
 
 #[allow(clippy::upper_case_acronyms)]
 type PID = i32;
 type NetworkInterface = String;
 
-#[derive(Debug)]
+/// Period between two ticks of the coarse histories, which cover about 8 minutes
+const COARSE_TICK_INTERVAL: Duration = Duration::from_secs(2);
+
+trait Tick {
+    fn at(&self) -> SystemTime;
+}
+
+#[derive(Clone, Debug)]
 struct TrackingTick {
     received: NumberOfBytes,
     send: NumberOfBytes,
     at: SystemTime,
 }
 
+impl TrackingTick {
+    /// Instantaneous (received, send) throughput since an older tick
+    fn throughput_since(&self, older: &TrackingTick) -> (BytesPerSecond, BytesPerSecond) {
+        let elapsed = self.at.duration_since(older.at).unwrap_or_default();
+        (
+            BytesPerSecond::new(self.received - older.received, elapsed),
+            BytesPerSecond::new(self.send - older.send, elapsed),
+        )
+    }
+}
+
+impl Tick for TrackingTick {
+    fn at(&self) -> SystemTime {
+        self.at
+    }
+}
+
+#[derive(Clone, Debug)]
+struct InterfaceTick {
+    traffic: TrackingTick,
+    counters: InterfaceCounters,
+}
+
+impl InterfaceTick {
+    fn rates_since(&self, older: &InterfaceTick) -> InterfaceRates {
+        let elapsed = self
+            .traffic
+            .at
+            .duration_since(older.traffic.at)
+            .unwrap_or_default();
+        let (received, send) = self.traffic.throughput_since(&older.traffic);
+        InterfaceRates::new(received, send, self.counters - older.counters, elapsed)
+    }
+}
+
+impl Tick for InterfaceTick {
+    fn at(&self) -> SystemTime {
+        self.traffic.at
+    }
+}
+
 pub struct BandwidthTracker {
     last_tick: SystemTime,
+    /// Time of every tick still in the histories
+    tick_times: HistoryBuffer<255, SystemTime>,
     refresh_counter: u32,
     over_time_per_pid: HashMap<PID, HistoryBuffer<255, TrackingTick>>,
-    over_time_per_io_interface: HashMap<NetworkInterface, HistoryBuffer<255, TrackingTick>>,
+    /// Highest instantaneous (received, send) throughput of each process since the monitoring started
+    peak_per_pid: HashMap<PID, (BytesPerSecond, BytesPerSecond)>,
+    over_time_per_io_interface: HashMap<NetworkInterface, HistoryBuffer<255, InterfaceTick>>,
+    /// One tick every `COARSE_TICK_INTERVAL`, for the rate windows longer than the histories
+    last_coarse_tick: SystemTime,
+    coarse_over_time_per_pid: HashMap<PID, HistoryBuffer<255, TrackingTick>>,
+    coarse_over_time_per_io_interface: HashMap<NetworkInterface, HistoryBuffer<255, InterfaceTick>>,
+    /// (received, send) bytes of each process when the totals were reset, and when it happened
+    totals_baseline: HashMap<PID, (NumberOfBytes, NumberOfBytes)>,
+    reset_at: Option<SystemTime>,
+    /// (received, send) bytes of each TCP connection at the last tick, by socket inode
+    connection_bytes: HashMap<u64, (NumberOfBytes, NumberOfBytes)>,
 }
 
 impl BandwidthTracker {
     pub fn new() -> BandwidthTracker {
+        let now = SystemTime::now();
         BandwidthTracker {
-            last_tick: SystemTime::now(),
+            last_tick: now,
+            tick_times: HistoryBuffer::init(now),
             refresh_counter: 0,
             over_time_per_pid: HashMap::new(),
+            peak_per_pid: HashMap::new(),
             over_time_per_io_interface: HashMap::new(),
+            last_coarse_tick: now,
+            coarse_over_time_per_pid: HashMap::new(),
+            coarse_over_time_per_io_interface: HashMap::new(),
+            totals_baseline: HashMap::new(),
+            reset_at: None,
+            connection_bytes: HashMap::new(),
         }
     }
 
-    pub fn refresh_tick(&mut self, packet_stats: &Map) {
-        let current_time = SystemTime::now();
+    /// Adds the counters of a tick to the histories
+    pub fn ingest(&mut self, snapshot: &Snapshot) {
+        let current_time = snapshot.at;
 
         if self.refresh_counter.is_multiple_of(10) {
             self.clear_dead_entries();
         }
 
-        packet_stats
-            .keys()
-            .for_each(self.append_new_tick_to_history(packet_stats, current_time));
+        for (pid, received, send) in &snapshot.processes {
+            self.append_new_tick_to_history(*pid, *received, *send, current_time);
+        }
+
+        self.append_new_tick_to_interface_history(snapshot, current_time);
+        self.connection_bytes = snapshot
+            .connections
+            .iter()
+            .map(|(inode, received, send)| (*inode, (*received, *send)))
+            .collect();
 
-        self.append_new_tick_to_interface_history(current_time);
+        let since_coarse_tick = current_time
+            .duration_since(self.last_coarse_tick)
+            .unwrap_or_default();
+        if since_coarse_tick >= COARSE_TICK_INTERVAL || self.refresh_counter == 0 {
+            self.append_coarse_ticks();
+            self.last_coarse_tick = current_time;
+        }
 
+        // the first tick can be older than the creation of the tracker when replaying
+        if self.refresh_counter == 0 {
+            self.tick_times = HistoryBuffer::init(current_time);
+        } else {
+            self.tick_times.push(current_time);
+        }
         self.refresh_counter += 1;
         self.last_tick = current_time;
     }
 
-    fn append_new_tick_to_history<'a>(
-        &'a mut self,
-        packet_stats: &'a Map,
+    pub fn last_tick(&self) -> SystemTime {
+        self.last_tick
+    }
+
+    pub fn oldest_tick(&self) -> SystemTime {
+        self.tick_times[0]
+    }
+
+    /// Time of the closest tick before `at`, `None` when it is the oldest tick of the history
+    pub fn previous_tick(&self, at: SystemTime) -> Option<SystemTime> {
+        self.tick_times
+            .into_iter()
+            .rev()
+            .find(|tick| **tick < at)
+            .copied()
+    }
+
+    /// Time of the closest tick after `at`, `None` when it is the last tick
+    pub fn next_tick(&self, at: SystemTime) -> Option<SystemTime> {
+        self.tick_times
+            .into_iter()
+            .find(|tick| **tick > at)
+            .copied()
+    }
+
+    fn append_new_tick_to_history(
+        &mut self,
+        pid: PID,
+        received: NumberOfBytes,
+        send: NumberOfBytes,
         current_time: SystemTime,
-    ) -> impl FnMut(Vec<u8>) + 'a {
-        move |key| {
-            let tmp = packet_stats
-                .lookup(&key, MapFlags::ANY)
-                .expect("error accessing map")
-                .expect("entry not found");
-
-            let bytes_received = i32::from_ne_bytes(
-                tmp[..4]
-                    .try_into()
-                    .expect("failed to convert bytes received the value to i32"),
-            )
-            .into();
-            let bytes_send = i32::from_ne_bytes(
-                tmp[4..]
-                    .try_into()
-                    .expect("failed to convert bytes send the value to i32"),
-            )
-            .into();
-            let pid = i32::from_ne_bytes(key.try_into().expect("failed to convert key to i32"));
-
-            let tick = TrackingTick {
-                received: bytes_received,
-                send: bytes_send,
-                at: current_time,
-            };
-            match self.over_time_per_pid.entry(pid) {
-                std::collections::hash_map::Entry::Occupied(mut entry) => {
-                    entry.get_mut().push(tick);
-                }
-                std::collections::hash_map::Entry::Vacant(vacant) => {
-                    vacant.insert(HistoryBuffer::init(tick));
-                }
-            };
-        }
+    ) {
+        let tick = TrackingTick {
+            received,
+            send,
+            at: current_time,
+        };
+        match self.over_time_per_pid.entry(pid) {
+            std::collections::hash_map::Entry::Occupied(mut entry) => {
+                let (received, send) = tick.throughput_since(entry.get().last());
+                let peak = self.peak_per_pid.entry(pid).or_default();
+                *peak = (peak.0.max(received), peak.1.max(send));
+                entry.get_mut().push(tick);
+            }
+            std::collections::hash_map::Entry::Vacant(vacant) => {
+                vacant.insert(HistoryBuffer::init(tick));
+            }
+        };
+    }
+
+    /// (received, send) bytes of a TCP connection since it was opened, when the source counts them
+    pub fn get_bytes_of_connection(&self, inode: u64) -> Option<(NumberOfBytes, NumberOfBytes)> {
+        self.connection_bytes.get(&inode).copied()
+    }
+
+    /// Whether the process still has a history, it is forgotten once its last tick left the history
+    pub fn is_tracked(&self, pid: PID) -> bool {
+        self.over_time_per_pid.contains_key(&pid)
+    }
+
+    /// Whether the process was still alive at the tick `at`
+    pub fn was_tracked_at(&self, pid: PID, at: SystemTime) -> bool {
+        self.over_time_per_pid
+            .get(&pid)
+            .is_some_and(|ticks| was_tracked_at(ticks.into_iter(), at))
     }
 
     /// Returns `None` when the process did not interacted with the network since the monitoring started
@@ -112,38 +229,174 @@ impl BandwidthTracker {
             .map(|ticks| ticks.last().send)
     }
 
-    pub fn get_throughput_over_duration(
+    /// (received, send) bytes since the start or the last reset, as they were at the tick `at`
+    ///
+    /// Returns `None` when the process did not interacted with the network before `at`
+    pub fn get_nbr_of_bytes_at(
         &self,
-        duration: Duration,
-    ) -> impl Iterator<Item = (PID, BytesPerSecond, BytesPerSecond)> + '_ {
-        let current_time = SystemTime::now();
+        pid: PID,
+        at: SystemTime,
+        since: TotalsSince,
+    ) -> Option<(NumberOfBytes, NumberOfBytes)> {
+        let tick = self
+            .over_time_per_pid
+            .get(&pid)?
+            .into_iter()
+            .rev()
+            .find(|tick| tick.at <= at)?;
 
+        // before the reset, the totals since the reset are the totals since the start
+        let baseline = match (since, self.reset_at) {
+            (TotalsSince::Reset, Some(reset_at)) if reset_at <= at => {
+                self.totals_baseline.get(&pid).copied().unwrap_or_default()
+            }
+            _ => Default::default(),
+        };
+        Some((tick.received - baseline.0, tick.send - baseline.1))
+    }
+
+    /// Zero the totals since reset of every process
+    pub fn reset_totals(&mut self) {
+        self.totals_baseline = self
+            .over_time_per_pid
+            .iter()
+            .map(|(pid, ticks)| (*pid, (ticks.last().received, ticks.last().send)))
+            .collect();
+        self.reset_at = Some(self.last_tick);
+    }
+
+    /// Instantaneous (received, send) throughput between each pair of consecutive ticks of the process
+    pub fn get_throughput_history_of_pid(
+        &self,
+        pid: PID,
+    ) -> Vec<(SystemTime, BytesPerSecond, BytesPerSecond)> {
+        let Some(history) = self.over_time_per_pid.get(&pid) else {
+            return vec![];
+        };
+
+        (1..history.len())
+            .map(|i| {
+                let (received, send) = history[i].throughput_since(&history[i - 1]);
+                (history[i].at, received, send)
+            })
+            .collect()
+    }
+
+    /// The last `points` instantaneous (received, send) throughputs of the process up to the tick `at`, the oldest first
+    pub fn get_recent_throughputs_of_pid_at(
+        &self,
+        pid: PID,
+        at: SystemTime,
+        points: usize,
+    ) -> Vec<(BytesPerSecond, BytesPerSecond)> {
+        let Some(history) = self.over_time_per_pid.get(&pid) else {
+            return vec![];
+        };
+        let recent_first: Vec<_> = history
+            .into_iter()
+            .rev()
+            .skip_while(|tick| tick.at > at)
+            .take(points + 1)
+            .collect();
+        recent_first
+            .windows(2)
+            .rev()
+            .map(|ticks| ticks[0].throughput_since(ticks[1]))
+            .collect()
+    }
+
+    /// Returns `None` when the process did not interacted with the network for two ticks
+    pub fn get_peak_throughput_of_pid(&self, pid: PID) -> Option<(BytesPerSecond, BytesPerSecond)> {
+        self.peak_per_pid.get(&pid).copied()
+    }
+
+    /// Throughput of the processes that were tracked at the tick `at`, measured over the `window` before it
+    pub fn get_throughput_at(
+        &self,
+        at: SystemTime,
+        window: RateWindow,
+    ) -> impl Iterator<Item = (PID, BytesPerSecond, BytesPerSecond)> + '_ {
         self.over_time_per_pid
             .iter()
-            .filter(|(_pid, ticks)| ticks.last().at == self.last_tick)
+            .filter(move |(_pid, ticks)| was_tracked_at(ticks.into_iter(), at))
             .map(move |(pid, ticks)| {
-                let mut ticks_in_window = ticks
-                    .into_iter()
-                    .rev()
-                    .take_while(|tick| tick.at + duration > current_time);
-
-                let most_recent_tick = ticks_in_window.next();
-                let oldest_tick = ticks_in_window.last();
-
-                match (most_recent_tick, oldest_tick) {
-                    (Some(t1), Some(t2)) => (
-                        *pid,
-                        BytesPerSecond::new(t1.received - t2.received, duration),
-                        BytesPerSecond::new(t1.send - t2.send, duration),
-                    ),
-                    _ => (*pid, BytesPerSecond::default(), BytesPerSecond::default()),
-                }
+                let coarse = self.coarse_over_time_per_pid.get(pid);
+                let (received, send) = window_bounds(ticks, coarse, window, at)
+                    .map(|(t1, t2)| t1.throughput_since(t2))
+                    .unwrap_or_default();
+                (*pid, received, send)
+            })
+    }
+
+    /// Interface throughput minus the throughput attributed to processes, as (received, send).
+    ///
+    /// What is left is traffic the probes did not see: kernel, forwarded or missed packets.
+    /// Can be negative when the probes count more than the interfaces, e.g. for failed sends.
+    pub fn get_unattributed_throughput_at(
+        &self,
+        at: SystemTime,
+        window: RateWindow,
+    ) -> (BytesPerSecond, BytesPerSecond) {
+        let (interfaces_received, interfaces_send) = self
+            .over_time_per_io_interface
+            .iter()
+            .filter(|(_, ticks)| was_tracked_at(ticks.into_iter().map(|tick| &tick.traffic), at))
+            .map(|(interface, ticks)| {
+                let coarse = self.coarse_over_time_per_io_interface.get(interface);
+                window_bounds(ticks, coarse, window, at)
+                    .map(|(t1, t2)| t1.traffic.throughput_since(&t2.traffic))
+                    .unwrap_or_default()
+            })
+            .fold(Default::default(), sum_throughputs);
+
+        let (processes_received, processes_send) = self
+            .get_throughput_at(at, window)
+            .map(|(_pid, received, send)| (received, send))
+            .fold(Default::default(), sum_throughputs);
+
+        (
+            interfaces_received - processes_received,
+            interfaces_send - processes_send,
+        )
+    }
+
+    /// (received, send) bytes of each process during the last tick
+    ///
+    /// A process seen for the first time counts all its bytes, they were all exchanged since the probes were loaded.
+    pub fn get_bytes_of_last_tick_per_pid(
+        &self,
+    ) -> impl Iterator<Item = (PID, NumberOfBytes, NumberOfBytes)> + '_ {
+        self.over_time_per_pid
+            .iter()
+            .filter(|(_, ticks)| ticks.last().at == self.last_tick)
+            .map(|(pid, ticks)| {
+                let last = ticks.last();
+                let (received, send) = match ticks.len() {
+                    1 => (last.received, last.send),
+                    len => bytes_since(last, &ticks[len - 2]),
+                };
+                (*pid, received, send)
             })
     }
 
+    /// (received, send) bytes of each interface during the last tick, nothing for a new interface
+    pub fn get_bytes_of_last_tick_per_interface(
+        &self,
+    ) -> impl Iterator<Item = (&NetworkInterface, NumberOfBytes, NumberOfBytes)> + '_ {
+        self.over_time_per_io_interface
+            .iter()
+            .filter(|(_, ticks)| ticks.len() > 1 && ticks.last().traffic.at == self.last_tick)
+            .map(|(interface, ticks)| {
+                let (received, send) =
+                    bytes_since(&ticks.last().traffic, &ticks[ticks.len() - 2].traffic);
+                (interface, received, send)
+            })
+    }
+
+    /// Instantaneous (received, send) throughput between each pair of consecutive ticks, per interface
     pub fn get_throughput_over_duration_per_interface(
         &self,
-    ) -> HashMap<NetworkInterface, Vec<BytesPerSecond>> {
+    ) -> HashMap<NetworkInterface, Vec<(SystemTime, BytesPerSecond, BytesPerSecond)>> {
         self.over_time_per_io_interface
             .iter()
             .map(|(interface, history)| {
@@ -153,13 +406,9 @@ impl BandwidthTracker {
                 let mut points: Vec<_> = Vec::new();
 
                 for i in 0..history.len() - 1 {
-                    let (t2, t1) = (&history[i], &history[i + 1]);
-
-                    let instantaneous = BytesPerSecond::new(
-                        t1.received - t2.received,
-                        t1.at.duration_since(t2.at).unwrap(),
-                    );
-                    points.push(instantaneous);
+                    let (t2, t1) = (&history[i].traffic, &history[i + 1].traffic);
+                    let (received, send) = t1.throughput_since(t2);
+                    points.push((t1.at, received, send));
                 }
 
                 (interface.clone(), points)
@@ -167,31 +416,348 @@ impl BandwidthTracker {
             .collect()
     }
 
+    /// Rates of all the counters at the last tick, measured over the `window` before it, per interface
+    pub fn get_interface_rates(
+        &self,
+        window: RateWindow,
+    ) -> HashMap<NetworkInterface, InterfaceRates> {
+        self.over_time_per_io_interface
+            .iter()
+            .filter(|(_interface, ticks)| ticks.last().traffic.at == self.last_tick)
+            .map(|(interface, ticks)| {
+                let coarse = self.coarse_over_time_per_io_interface.get(interface);
+                let rates = window_bounds(ticks, coarse, window, self.last_tick)
+                    .map(|(t1, t2)| t1.rates_since(t2))
+                    .unwrap_or_default();
+                (interface.clone(), rates)
+            })
+            .collect()
+    }
+
+    /// Rates of all the counters between each pair of consecutive ticks, per interface
+    pub fn get_interface_rates_history(&self) -> HashMap<NetworkInterface, Vec<InterfaceRates>> {
+        self.over_time_per_io_interface
+            .iter()
+            .map(|(interface, history)| {
+                let rates = (1..history.len())
+                    .map(|i| history[i].rates_since(&history[i - 1]))
+                    .collect();
+                (interface.clone(), rates)
+            })
+            .collect()
+    }
+
+    /// Forgets the interfaces gone at the last tick, and the processes whose last tick left the history
+    ///
+    /// The exited processes stay around while their ticks can still be viewed by pausing or scrubbing.
     fn clear_dead_entries(&mut self) {
+        let oldest_tick = self.oldest_tick();
         self.over_time_per_pid
-            .retain(|_pid, buffer| buffer.last().at == self.last_tick);
+            .retain(|_pid, buffer| buffer.last().at >= oldest_tick);
+        self.peak_per_pid
+            .retain(|pid, _peak| self.over_time_per_pid.contains_key(pid));
 
         self.over_time_per_io_interface
-            .retain(|_interface, buffer| buffer.last().at == self.last_tick);
+            .retain(|_interface, buffer| buffer.last().traffic.at == self.last_tick);
+
+        self.coarse_over_time_per_pid
+            .retain(|pid, _| self.over_time_per_pid.contains_key(pid));
+        self.coarse_over_time_per_io_interface
+            .retain(|interface, _| self.over_time_per_io_interface.contains_key(interface));
+        self.totals_baseline
+            .retain(|pid, _| self.over_time_per_pid.contains_key(pid));
     }
 
-    fn append_new_tick_to_interface_history(&mut self, current_time: SystemTime) {
-        let status = procfs::net::dev_status().unwrap();
+    fn append_coarse_ticks(&mut self) {
+        for (pid, ticks) in &self.over_time_per_pid {
+            append_to(
+                &mut self.coarse_over_time_per_pid,
+                *pid,
+                ticks.last().clone(),
+            );
+        }
+        for (interface, ticks) in &self.over_time_per_io_interface {
+            append_to(
+                &mut self.coarse_over_time_per_io_interface,
+                interface.clone(),
+                ticks.last().clone(),
+            );
+        }
+    }
 
-        status.iter().for_each(|(interface, value)| {
-            let tick = TrackingTick {
-                received: value.recv_bytes.into(),
-                send: value.sent_bytes.into(),
-                at: current_time,
-            };
-            match self.over_time_per_io_interface.entry(interface.clone()) {
-                std::collections::hash_map::Entry::Occupied(mut entry) => {
-                    entry.get_mut().push(tick);
-                }
-                std::collections::hash_map::Entry::Vacant(vacant) => {
-                    vacant.insert(HistoryBuffer::init(tick));
-                }
+    fn append_new_tick_to_interface_history(
+        &mut self,
+        snapshot: &Snapshot,
+        current_time: SystemTime,
+    ) {
+        for (interface, received, send, counters) in &snapshot.interfaces {
+            let tick = InterfaceTick {
+                traffic: TrackingTick {
+                    received: *received,
+                    send: *send,
+                    at: current_time,
+                },
+                counters: *counters,
             };
+            append_to(
+                &mut self.over_time_per_io_interface,
+                interface.clone(),
+                tick,
+            );
+        }
+    }
+}
+
+fn append_to<K: std::hash::Hash + Eq, T>(
+    histories: &mut HashMap<K, HistoryBuffer<255, T>>,
+    key: K,
+    tick: T,
+) {
+    match histories.entry(key) {
+        std::collections::hash_map::Entry::Occupied(mut entry) => entry.get_mut().push(tick),
+        std::collections::hash_map::Entry::Vacant(vacant) => {
+            vacant.insert(HistoryBuffer::init(tick));
+        }
+    }
+}
+
+/// (most recent, oldest) ticks of the `window` ending at the tick `at`
+///
+/// The oldest tick is looked up in the coarse history as well, it goes further back in time.
+/// Returns `None` when there are not two ticks in the window.
+fn window_bounds<'a, T: Tick>(
+    ticks: &'a HistoryBuffer<255, T>,
+    coarse_ticks: Option<&'a HistoryBuffer<255, T>>,
+    window: RateWindow,
+    at: SystemTime,
+) -> Option<(&'a T, &'a T)> {
+    let mut recent_first = ticks.into_iter().rev().skip_while(|tick| tick.at() > at);
+    let most_recent = recent_first.next()?;
+
+    let oldest = match window {
+        RateWindow::Instant => recent_first.next(),
+        RateWindow::Average(duration) => {
+            let in_window = |tick: &&T| tick.at() + duration > at && tick.at() < most_recent.at();
+            let oldest = ticks.into_iter().find(in_window);
+            let coarse_oldest = coarse_ticks.and_then(|coarse| coarse.into_iter().find(in_window));
+            oldest
+                .into_iter()
+                .chain(coarse_oldest)
+                .min_by_key(|tick| tick.at())
+        }
+    }?;
+    Some((most_recent, oldest))
+}
+
+/// Whether the history has a tick at exactly `at`, i.e. the entry was still alive at that time
+fn was_tracked_at<'a>(
+    ticks: impl DoubleEndedIterator<Item = &'a TrackingTick>,
+    at: SystemTime,
+) -> bool {
+    ticks
+        .rev()
+        .find(|tick| tick.at <= at)
+        .is_some_and(|tick| tick.at == at)
+}
+
+/// Counters going backward were reset, nothing is counted for them
+fn bytes_since(tick: &TrackingTick, older: &TrackingTick) -> (NumberOfBytes, NumberOfBytes) {
+    let zero = NumberOfBytes::default();
+    (
+        (tick.received - older.received).max(zero),
+        (tick.send - older.send).max(zero),
+    )
+}
+
+fn sum_throughputs(
+    (received, send): (BytesPerSecond, BytesPerSecond),
+    (other_received, other_send): (BytesPerSecond, BytesPerSecond),
+) -> (BytesPerSecond, BytesPerSecond) {
+    (received + other_received, send + other_send)
+}
+
+#[cfg(test)]
+mod tests_tracker {
+    use std::time::{Duration, SystemTime};
+
+    use crate::bandwidth_tracker::source::Snapshot;
+
+    use super::{window_bounds, BandwidthTracker, HistoryBuffer, RateWindow, TrackingTick};
+
+    fn history(start: SystemTime, seconds: &[u64]) -> HistoryBuffer<255, TrackingTick> {
+        let tick = |second: u64| TrackingTick {
+            received: (second as i32 * 100).into(),
+            send: 0.into(),
+            at: start + Duration::from_secs(second),
+        };
+        let mut history = HistoryBuffer::init(tick(seconds[0]));
+        seconds[1..]
+            .iter()
+            .for_each(|second| history.push(tick(*second)));
+        history
+    }
+
+    #[test]
+    fn test_window_bounds() {
+        let start = SystemTime::UNIX_EPOCH;
+        let ticks = history(start, &[10, 11, 12, 13]);
+        let at = start + Duration::from_secs(13);
+
+        let (recent, oldest) = window_bounds(&ticks, None, RateWindow::Instant, at).unwrap();
+        assert_eq!(
+            (recent.at, oldest.at),
+            (at, start + Duration::from_secs(12))
+        );
+
+        let window = RateWindow::Average(Duration::from_secs(3));
+        let (_, oldest) = window_bounds(&ticks, None, window, at).unwrap();
+        assert_eq!(oldest.at, start + Duration::from_secs(11));
+        assert!(recent.throughput_since(oldest).0 == 100.0.into());
+    }
+
+    #[test]
+    fn test_long_windows_use_the_coarse_history() {
+        let start = SystemTime::UNIX_EPOCH;
+        let ticks = history(start, &[10, 11, 12, 13]);
+        let coarse = history(start, &[0, 2, 4, 6, 8, 10, 12]);
+        let at = start + Duration::from_secs(13);
+
+        let window = RateWindow::Average(Duration::from_secs(10));
+        let (_, oldest) = window_bounds(&ticks, Some(&coarse), window, at).unwrap();
+
+        assert_eq!(oldest.at, start + Duration::from_secs(4));
+    }
+
+    /// One snapshot per second from `start`, with the processes of `pids_at(second)` receiving 100 bytes per second
+    fn ingest_seconds(
+        tracker: &mut BandwidthTracker,
+        start: SystemTime,
+        seconds: std::ops::Range<u64>,
+        pids_at: impl Fn(u64) -> Vec<i32>,
+    ) {
+        for second in seconds {
+            tracker.ingest(&Snapshot {
+                at: start + Duration::from_secs(second),
+                processes: pids_at(second)
+                    .into_iter()
+                    .map(|pid| (pid, (second * 100).into(), 0.into()))
+                    .collect(),
+                interfaces: Vec::new(),
+                connections: Vec::new(),
+                identities: Vec::new(),
+            });
+        }
+    }
+
+    #[test]
+    fn test_previous_and_next_ticks() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut tracker = BandwidthTracker::new();
+        ingest_seconds(&mut tracker, start, 0..4, |_| vec![42]);
+        let second = |second| start + Duration::from_secs(second);
+
+        assert_eq!(tracker.previous_tick(second(2)), Some(second(1)));
+        assert_eq!(tracker.previous_tick(second(0)), None);
+        assert_eq!(tracker.next_tick(second(1)), Some(second(2)));
+        assert_eq!(tracker.next_tick(second(3)), None);
+    }
+
+    #[test]
+    fn test_throughput_at_past_ticks() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut tracker = BandwidthTracker::new();
+        // 43 is missing at the third tick
+        ingest_seconds(&mut tracker, start, 0..5, |second| match second {
+            2 => vec![42],
+            _ => vec![42, 43],
         });
+        let pids_at = |second| {
+            let at = start + Duration::from_secs(second);
+            let mut pids: Vec<_> = tracker
+                .get_throughput_at(at, RateWindow::Instant)
+                .map(|(pid, received, _)| (pid, f64::from(received)))
+                .collect();
+            pids.sort_by_key(|(pid, _)| *pid);
+            pids
+        };
+
+        assert_eq!(pids_at(1), [(42, 100.), (43, 100.)]);
+        assert_eq!(pids_at(2), [(42, 100.)]);
+        assert_eq!(pids_at(4), [(42, 100.), (43, 100.)]);
+    }
+
+    #[test]
+    fn test_recent_throughputs_up_to_a_tick() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut tracker = BandwidthTracker::new();
+        for second in 0..10 {
+            tracker.ingest(&Snapshot {
+                at: start + Duration::from_secs(second),
+                processes: vec![(42, (second * second * 100).into(), 0.into())],
+                interfaces: Vec::new(),
+                connections: Vec::new(),
+                identities: Vec::new(),
+            });
+        }
+        let received = |at, points| {
+            tracker
+                .get_recent_throughputs_of_pid_at(42, start + Duration::from_secs(at), points)
+                .into_iter()
+                .map(|(received, _)| f64::from(received))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(received(5, 3), [500., 700., 900.]);
+        assert_eq!(received(1, 3), [100.]);
+        assert!(received(9, 0).is_empty());
+        assert!(tracker
+            .get_recent_throughputs_of_pid_at(43, tracker.last_tick(), 3)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_exited_processes_are_kept_while_in_the_history() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut tracker = BandwidthTracker::new();
+        ingest_seconds(&mut tracker, start, 0..5, |_| vec![42, 43]);
+        // 43 exits, the dead entries are cleared every 10 ticks
+        ingest_seconds(&mut tracker, start, 5..30, |_| vec![42]);
+
+        assert!(tracker.is_tracked(43));
+        let at = start + Duration::from_secs(3);
+        assert_eq!(
+            tracker.get_throughput_at(at, RateWindow::Instant).count(),
+            2
+        );
+        let last = tracker.last_tick();
+        assert_eq!(
+            tracker.get_throughput_at(last, RateWindow::Instant).count(),
+            1
+        );
+
+        ingest_seconds(&mut tracker, start, 30..300, |_| vec![42]);
+        assert!(!tracker.is_tracked(43));
+        assert!(tracker.is_tracked(42));
+    }
+
+    #[test]
+    fn test_ingest_snapshots() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut tracker = BandwidthTracker::new();
+        for second in 0..3 {
+            tracker.ingest(&Snapshot {
+                at: start + Duration::from_secs(second),
+                processes: vec![(42, (second * 500).into(), 0.into())],
+                interfaces: Vec::new(),
+                connections: Vec::new(),
+                identities: Vec::new(),
+            });
+        }
+
+        assert_eq!(tracker.oldest_tick(), start);
+        let at = tracker.last_tick();
+        let throughputs: Vec<_> = tracker.get_throughput_at(at, RateWindow::Instant).collect();
+        assert_eq!(throughputs.len(), 1);
+        assert!(throughputs[0].1 == 500.0.into());
     }
 }
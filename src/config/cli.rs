@@ -0,0 +1,127 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use clap::{ArgGroup, Parser};
+
+use crate::{
+    bandwidth_tracker::{measure::RateWindow, source::SourceKind},
+    export::stream::{parse_size, Format},
+    report::{parse_time, SummaryFormat},
+};
+
+use super::{pattern::Pattern, ChartPlacement, ThemeName};
+
+/// Per-process network usage monitor
+#[derive(Parser)]
+#[command(version, about, group(ArgGroup::new("sample").args(["duration", "once"])))]
+pub struct Cli {
+    /// Path of the config file, defaults to `~/.config/net-monitor/config.toml`
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Only chart the interfaces matching the pattern, a glob or a `/regex/` (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    pub include_interface: Vec<Pattern>,
+
+    /// Do not chart the interfaces matching the pattern, a glob or a `/regex/` (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    pub exclude_interface: Vec<Pattern>,
+
+    /// Hide the interfaces that are down or did not see any traffic
+    #[arg(long)]
+    pub hide_idle_interfaces: bool,
+
+    /// Show bits per second instead of bytes per second
+    #[arg(long)]
+    pub bits: bool,
+
+    /// Use the powers of 1024 (KiB, MiB) instead of the powers of 1000 (KB, MB)
+    #[arg(long)]
+    pub binary: bool,
+
+    /// Always show this unit, like `MB`, `MiB` or `Mbit`
+    #[arg(long)]
+    pub unit: Option<String>,
+
+    /// Show plain numbers of bytes (or bits) without unit
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Window of the rates: instant, 1s, 5s, 30s or 5m
+    #[arg(long, value_name = "WINDOW")]
+    pub rate_window: Option<RateWindow>,
+
+    /// Where the chart goes relatively to the process table
+    #[arg(long, value_enum)]
+    pub chart: Option<ChartPlacement>,
+
+    /// Colors of the TUI, defaults to dark or to monochrome when `NO_COLOR` is set
+    #[arg(long, value_enum)]
+    pub theme: Option<ThemeName>,
+
+    /// Where the traffic of the processes comes from, the probes fall back on sock-diag then proc when they cannot be loaded
+    #[arg(long, value_enum, default_value = "probes", conflicts_with = "replay")]
+    pub source: SourceKind,
+
+    /// Record the session to this file, to replay it later with `--replay`
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Replay a recorded session instead of monitoring this host, no privileges are needed
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// Speed of the replay, from 0.25 to 32
+    #[arg(long, value_name = "SPEED", default_value_t = 1.)]
+    pub replay_speed: f64,
+
+    /// Write the usage of the processes and interfaces to this SQLite database
+    #[arg(long, value_name = "FILE")]
+    pub export_sqlite: Option<PathBuf>,
+
+    /// Period covered by each exported row, 10s by default
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub export_interval: Option<Duration>,
+
+    /// Print the usage rows in this format instead of showing the TUI
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+
+    /// Write the rows of `--format` to this file instead of the standard output
+    #[arg(long, value_name = "FILE", requires = "format")]
+    pub output: Option<PathBuf>,
+
+    /// Rotate the output file once it reaches this size, like `100MB`
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, requires = "output")]
+    pub rotate_size: Option<u64>,
+
+    /// Number of rotated output files kept
+    #[arg(long, value_name = "COUNT", default_value_t = 5)]
+    pub rotate_keep: usize,
+
+    /// Sample for this time, like `10s` or `5m`, then print a summary instead of showing the TUI
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration, conflicts_with = "replay")]
+    pub duration: Option<Duration>,
+
+    /// Sample for one second, then print a summary
+    #[arg(long, conflicts_with_all = ["duration", "replay"])]
+    pub once: bool,
+
+    /// Number of processes in the summary of `--duration` or `--once`
+    #[arg(long, value_name = "COUNT", default_value_t = 10, requires = "sample")]
+    pub top: usize,
+
+    /// Format of the summary of `--duration` or `--once`
+    #[arg(long, value_enum, default_value_t, requires = "sample")]
+    pub summary: SummaryFormat,
+
+    /// Print what used the network from this time, `HH:MM` or `YYYY-MM-DD HH:MM`, and exit
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub history_at: Option<SystemTime>,
+
+    /// Length of the period printed by `--history-at`, like `10m` or `2h`
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration, default_value = "10m")]
+    pub history_span: Duration,
+}
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, error::Error, fs, path::PathBuf, time::Duration};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    bandwidth_tracker::{
+        alerts::AlertsConfig,
+        bytes::{parse_rate, BytesPerSecond, UnitFormat},
+        measure::RateWindow,
+        quotas::QuotasConfig,
+        store::HistoryConfig,
+    },
+    export::{stream::StreamConfig, ExportConfig},
+};
+
+use self::{cli::Cli, pattern::Pattern};
+
+pub mod cli;
+pub mod pattern;
+
+/// Settings read from the config file, the command line flags are merged on top of it
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub interfaces: InterfacesConfig,
+    pub display: DisplayConfig,
+    pub alerts: AlertsConfig,
+    pub quotas: QuotasConfig,
+    /// Long-term history of the programs and interfaces
+    pub history: HistoryConfig,
+    /// Usage rows written for other tools
+    pub export: ExportConfig,
+    /// Keys of the actions by action name, replacing the default keys of these actions
+    pub keybindings: HashMap<String, KeyList>,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    /// Show bits per second instead of bytes per second
+    pub bits: bool,
+    /// Use the powers of 1024 (`KiB`, `MiB`) instead of the powers of 1000
+    pub binary: bool,
+    /// Always show this unit, like `MB` or `Mbit`
+    pub unit: Option<String>,
+    /// Show plain numbers without unit
+    pub raw: bool,
+    /// Window of the rates: `instant`, `1s`, `5s`, `30s` or `5m`
+    pub rate_window: RateWindow,
+    /// Where the chart goes relatively to the process table
+    pub chart: ChartPlacement,
+    /// Defaults to `dark`, or `monochrome` when `NO_COLOR` is set
+    pub theme: Option<ThemeName>,
+    /// Rates above which the process rows are colored, like `["100KB", "1MB", "10MB"]`
+    pub heat_thresholds: Vec<String>,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig {
+            bits: false,
+            binary: false,
+            unit: None,
+            raw: false,
+            rate_window: RateWindow::default(),
+            chart: ChartPlacement::default(),
+            theme: None,
+            heat_thresholds: ["100KB", "1MB", "10MB"].map(String::from).to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChartPlacement {
+    /// Beside the table on wide terminals, below otherwise
+    #[default]
+    Auto,
+    Below,
+    Beside,
+}
+
+impl DisplayConfig {
+    pub fn unit_format(&self) -> Result<UnitFormat, String> {
+        let fixed_unit = self
+            .unit
+            .as_deref()
+            .map(|unit| unit.parse().map_err(|_| format!("unknown unit `{unit}`")))
+            .transpose()?;
+        Ok(UnitFormat {
+            bits: self.bits,
+            binary: self.binary,
+            fixed_unit,
+            raw: self.raw,
+        })
+    }
+
+    /// Thresholds sorted from the lowest, a trailing `/s` is allowed
+    pub fn heat_thresholds(&self) -> Result<Vec<BytesPerSecond>, String> {
+        let mut thresholds = self
+            .heat_thresholds
+            .iter()
+            .map(|threshold| {
+                parse_rate(threshold).map_err(|error| format!("heat threshold: {error}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        thresholds.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(thresholds)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemeName {
+    Dark,
+    Light,
+    HighContrast,
+    /// No colors, only bold, italic and underlined texts
+    Monochrome,
+}
+
+/// One key or a list of keys, like `"q"` or `["q", "ctrl+c"]`
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        match self {
+            KeyList::One(key) => std::slice::from_ref(key).iter(),
+            KeyList::Many(keys) => keys.iter(),
+        }
+        .map(String::as_str)
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InterfacesConfig {
+    /// When not empty, only the interfaces matching one of the patterns are charted
+    pub include: Vec<Pattern>,
+    /// Interfaces matching one of the patterns are not charted
+    pub exclude: Vec<Pattern>,
+    /// Hide the interfaces that are down or did not see any traffic
+    pub hide_idle: bool,
+}
+
+impl Config {
+    pub fn load(cli: &Cli) -> Result<Config, Box<dyn Error>> {
+        let mut config = match cli.config.clone().or_else(default_config_path) {
+            Some(path) if path.exists() || cli.config.is_some() => {
+                let content = fs::read_to_string(&path)
+                    .map_err(|err| format!("cannot read {}: {err}", path.display()))?;
+                toml::from_str(&content)
+                    .map_err(|err| format!("invalid config {}: {err}", path.display()))?
+            }
+            _ => Config::default(),
+        };
+
+        config
+            .interfaces
+            .include
+            .extend(cli.include_interface.iter().cloned());
+        config
+            .interfaces
+            .exclude
+            .extend(cli.exclude_interface.iter().cloned());
+        config.interfaces.hide_idle |= cli.hide_idle_interfaces;
+        config.display.bits |= cli.bits;
+        config.display.binary |= cli.binary;
+        config.display.raw |= cli.raw;
+        if cli.theme.is_some() {
+            config.display.theme = cli.theme;
+        }
+        if let Some(chart) = cli.chart {
+            config.display.chart = chart;
+        }
+        if let Some(rate_window) = cli.rate_window {
+            config.display.rate_window = rate_window;
+        }
+        if cli.unit.is_some() {
+            config.display.unit = cli.unit.clone();
+        }
+        if cli.export_sqlite.is_some() {
+            config.export.sqlite = cli.export_sqlite.clone();
+        }
+        if let Some(interval) = cli.export_interval {
+            config.export.interval = interval;
+        }
+        config.export.stream = cli.format.map(|format| StreamConfig {
+            format,
+            output: cli.output.clone(),
+            rotate_size: cli.rotate_size,
+            rotate_keep: cli.rotate_keep,
+        });
+
+        Ok(config)
+    }
+}
+
+/// Durations like `10s` or `1m`
+pub fn deserialize_duration<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Duration, D::Error> {
+    let duration = String::deserialize(deserializer)?;
+    humantime::parse_duration(&duration).map_err(|error| {
+        serde::de::Error::custom(format!("invalid duration `{duration}`: {error}"))
+    })
+}
+
+/// `$XDG_STATE_HOME/net-monitor`, falling back on `~/.local/state/net-monitor`, for the files kept across runs
+pub fn state_dir() -> Option<PathBuf> {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(state_dir.join("net-monitor"))
+}
+
+/// `$XDG_CONFIG_HOME/net-monitor/config.toml`, falling back on `~/.config/net-monitor/config.toml`
+fn default_config_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("net-monitor").join("config.toml"))
+}
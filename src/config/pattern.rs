@@ -0,0 +1,120 @@
+use std::{fmt::Display, str::FromStr};
+
+use regex::Regex;
+use serde::Deserialize;
+
+/// Name pattern, either a glob (`veth*`) or a regex surrounded by slashes (`/^veth[0-9a-f]+$/`)
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Pattern {
+    source: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    pub fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut in_class = false;
+    for c in glob.chars() {
+        match c {
+            '*' if !in_class => regex.push_str(".*"),
+            '?' if !in_class => regex.push('.'),
+            '[' if !in_class => {
+                in_class = true;
+                regex.push('[');
+            }
+            ']' if in_class => {
+                in_class = false;
+                regex.push(']');
+            }
+            '!' if in_class && regex.ends_with('[') => regex.push('^'),
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+impl FromStr for Pattern {
+    type Err = regex::Error;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let regex = match source
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+        {
+            Some(regex) => Regex::new(regex)?,
+            None => Regex::new(&glob_to_regex(source))?,
+        };
+
+        Ok(Pattern {
+            source: source.to_string(),
+            regex,
+        })
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests_pattern {
+    use super::Pattern;
+
+    #[test]
+    fn test_glob_matches_whole_name() {
+        let pattern: Pattern = "veth*".parse().unwrap();
+
+        assert!(pattern.matches("veth1a2b"));
+        assert!(!pattern.matches("docker0veth"));
+    }
+
+    #[test]
+    fn test_glob_single_char_and_class() {
+        let pattern: Pattern = "eth?".parse().unwrap();
+        let class: Pattern = "wl[!o]*".parse().unwrap();
+
+        assert!(pattern.matches("eth0"));
+        assert!(!pattern.matches("eth10"));
+        assert!(class.matches("wlp3s0"));
+        assert!(!class.matches("wlo1"));
+    }
+
+    #[test]
+    fn test_glob_escapes_regex_characters() {
+        let pattern: Pattern = "br.lan".parse().unwrap();
+
+        assert!(pattern.matches("br.lan"));
+        assert!(!pattern.matches("br0lan"));
+    }
+
+    #[test]
+    fn test_slashes_make_a_regex() {
+        let pattern: Pattern = "/^(lo|docker[0-9]+)$/".parse().unwrap();
+
+        assert!(pattern.matches("lo"));
+        assert!(pattern.matches("docker0"));
+        assert!(!pattern.matches("eth0"));
+    }
+
+    #[test]
+    fn test_invalid_regex_is_an_error() {
+        assert!("/(/".parse::<Pattern>().is_err());
+    }
+}
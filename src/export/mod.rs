@@ -0,0 +1,272 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::{
+    bandwidth_tracker::{bytes::NumberOfBytes, tracker::BandwidthTracker},
+    config::deserialize_duration,
+    system::process::ProcessIdentity,
+};
+
+pub mod sqlite;
+pub mod stream;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportConfig {
+    /// SQLite database receiving the usage of the processes and interfaces
+    pub sqlite: Option<PathBuf>,
+    /// Period covered by each row, like `10s` or `1m`
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub interval: Duration,
+    /// Rows printed instead of showing the TUI, only set from the command line
+    #[serde(skip)]
+    pub stream: Option<stream::StreamConfig>,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        ExportConfig {
+            sqlite: None,
+            interval: Duration::from_secs(10),
+            stream: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ProcessUsage {
+    pub pid: i32,
+    pub comm: String,
+    pub user: String,
+    pub cgroup: String,
+    pub received: NumberOfBytes,
+    pub send: NumberOfBytes,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InterfaceUsage {
+    pub interface: String,
+    pub received: NumberOfBytes,
+    pub send: NumberOfBytes,
+}
+
+/// Bytes exchanged by the processes and interfaces during one period, ending `at`
+#[derive(Clone, Debug, Serialize)]
+pub struct UsagePeriod {
+    #[serde(rename = "timestamp", serialize_with = "serialize_epoch_seconds")]
+    pub at: SystemTime,
+    pub processes: Vec<ProcessUsage>,
+    pub interfaces: Vec<InterfaceUsage>,
+}
+
+impl UsagePeriod {
+    pub fn epoch_seconds(&self) -> u64 {
+        epoch_seconds(self.at)
+    }
+}
+
+fn epoch_seconds(at: SystemTime) -> u64 {
+    at.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn serialize_epoch_seconds<S: Serializer>(
+    at: &SystemTime,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(epoch_seconds(*at))
+}
+
+/// Where the periods go: a database, or rows in one of the text formats
+pub trait UsageWriter {
+    fn write(&mut self, period: &UsagePeriod) -> Result<(), String>;
+}
+
+/// Adds up the ticks of the tracker into periods, and hands them to the writers
+pub struct UsageExport {
+    interval: Duration,
+    /// Start of the period being added up, `None` before the first tick
+    started: Option<SystemTime>,
+    processes: HashMap<i32, ProcessUsage>,
+    interfaces: BTreeMap<String, (NumberOfBytes, NumberOfBytes)>,
+    last_tick: SystemTime,
+    writers: Vec<Box<dyn UsageWriter>>,
+}
+
+impl UsageExport {
+    /// Export to the database and the stream of the config, `None` when there is neither
+    pub fn open(config: &ExportConfig) -> Result<Option<UsageExport>, String> {
+        let mut export = UsageExport::new(config.interval);
+        if let Some(path) = &config.sqlite {
+            export
+                .writers
+                .push(Box::new(sqlite::SqliteWriter::open(path)?));
+        }
+        if let Some(stream) = &config.stream {
+            export
+                .writers
+                .push(Box::new(stream::StreamWriter::open(stream)?));
+        }
+        Ok((!export.writers.is_empty()).then_some(export))
+    }
+
+    /// Without writers, the periods are only returned by `add_tick`
+    pub fn new(interval: Duration) -> UsageExport {
+        UsageExport {
+            interval,
+            started: None,
+            processes: HashMap::new(),
+            interfaces: BTreeMap::new(),
+            last_tick: SystemTime::UNIX_EPOCH,
+            writers: Vec::new(),
+        }
+    }
+
+    /// Adds the bytes of the last tick, and writes the period once it is over
+    pub fn record(
+        &mut self,
+        tracker: &BandwidthTracker,
+        identity_of: impl Fn(i32) -> Option<ProcessIdentity>,
+    ) -> Result<(), String> {
+        match self.add_tick(tracker, identity_of) {
+            Some(period) => self.write(period),
+            None => Ok(()),
+        }
+    }
+
+    /// The period ended by this tick, if any
+    pub fn add_tick(
+        &mut self,
+        tracker: &BandwidthTracker,
+        identity_of: impl Fn(i32) -> Option<ProcessIdentity>,
+    ) -> Option<UsagePeriod> {
+        let at = tracker.last_tick();
+        self.last_tick = at;
+        let started = *self.started.get_or_insert(at);
+
+        for (pid, received, send) in tracker.get_bytes_of_last_tick_per_pid() {
+            if received == NumberOfBytes::default() && send == NumberOfBytes::default() {
+                continue;
+            }
+            let usage = self.processes.entry(pid).or_insert_with(|| ProcessUsage {
+                pid,
+                ..Default::default()
+            });
+            // the identity may only be readable after the first bytes
+            if usage.comm.is_empty() {
+                if let Some(identity) = identity_of(pid) {
+                    usage.comm = identity.name;
+                    usage.user = identity.user;
+                    usage.cgroup = identity.cgroup;
+                }
+            }
+            usage.received = usage.received + received;
+            usage.send = usage.send + send;
+        }
+        for (interface, received, send) in tracker.get_bytes_of_last_tick_per_interface() {
+            let bytes = self.interfaces.entry(interface.clone()).or_default();
+            bytes.0 = bytes.0 + received;
+            bytes.1 = bytes.1 + send;
+        }
+
+        let elapsed = at.duration_since(started).unwrap_or_default();
+        (elapsed >= self.interval).then(|| self.take_period())
+    }
+
+    fn take_period(&mut self) -> UsagePeriod {
+        self.started = Some(self.last_tick);
+        let mut processes: Vec<_> = self.processes.drain().map(|(_, usage)| usage).collect();
+        processes.sort_by_key(|usage| usage.pid);
+        UsagePeriod {
+            at: self.last_tick,
+            processes,
+            interfaces: std::mem::take(&mut self.interfaces)
+                .into_iter()
+                .filter(|(_, (received, send))| {
+                    *received != NumberOfBytes::default() || *send != NumberOfBytes::default()
+                })
+                .map(|(interface, (received, send))| InterfaceUsage {
+                    interface,
+                    received,
+                    send,
+                })
+                .collect(),
+        }
+    }
+
+    fn write(&mut self, period: UsagePeriod) -> Result<(), String> {
+        self.writers
+            .iter_mut()
+            .try_for_each(|writer| writer.write(&period))
+    }
+}
+
+impl Drop for UsageExport {
+    fn drop(&mut self) {
+        // the last, partial, period is written too
+        if self.started.is_some() && !(self.processes.is_empty() && self.interfaces.is_empty()) {
+            let period = self.take_period();
+            let _ = self.write(period);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_export {
+    use std::time::{Duration, SystemTime};
+
+    use crate::{
+        bandwidth_tracker::{
+            interface_counters::InterfaceCounters, source::Snapshot, tracker::BandwidthTracker,
+        },
+        system::process::ProcessIdentity,
+    };
+
+    use super::UsageExport;
+
+    fn identity(pid: i32) -> Option<ProcessIdentity> {
+        Some(ProcessIdentity {
+            name: format!("process-{pid}"),
+            cmdline: String::new(),
+            user: "root".to_string(),
+            cgroup: "/system.slice".to_string(),
+        })
+    }
+
+    #[test]
+    fn test_ticks_are_added_up_in_periods() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let mut tracker = BandwidthTracker::new();
+        let mut export = UsageExport::new(Duration::from_secs(10));
+        let mut periods = Vec::new();
+        for second in 0..=20 {
+            tracker.ingest(&Snapshot {
+                at: start + Duration::from_secs(second),
+                processes: vec![(42, 0.into(), (second * 100).into())],
+                interfaces: vec![(
+                    "eth0".to_string(),
+                    (second * 1000).into(),
+                    0.into(),
+                    InterfaceCounters::default(),
+                )],
+                connections: Vec::new(),
+                identities: Vec::new(),
+            });
+            periods.extend(export.add_tick(&tracker, identity));
+        }
+
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0].at, start + Duration::from_secs(10));
+        assert_eq!(periods[0].processes.len(), 1);
+        assert_eq!(periods[0].processes[0].comm, "process-42");
+        assert_eq!(periods[0].processes[0].send, 1000.into());
+        assert_eq!(periods[1].processes[0].send, 1000.into());
+        assert_eq!(periods[1].interfaces[0].received, 10_000.into());
+    }
+}
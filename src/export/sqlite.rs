@@ -0,0 +1,184 @@
+use std::{
+    path::Path,
+    sync::mpsc::{self, Receiver, Sender},
+    thread::JoinHandle,
+};
+
+use rusqlite::{params, Connection};
+
+use super::{UsagePeriod, UsageWriter};
+
+/// Bytes are the ones exchanged during the period ending at `timestamp`, in seconds since the epoch
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS process_usage (
+    timestamp INTEGER NOT NULL,
+    pid INTEGER NOT NULL,
+    comm TEXT NOT NULL,
+    user TEXT NOT NULL,
+    cgroup TEXT NOT NULL,
+    received INTEGER NOT NULL,
+    send INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS process_usage_by_time ON process_usage (timestamp);
+CREATE INDEX IF NOT EXISTS process_usage_by_comm ON process_usage (comm, timestamp);
+CREATE INDEX IF NOT EXISTS process_usage_by_user ON process_usage (user, timestamp);
+
+CREATE TABLE IF NOT EXISTS interface_usage (
+    timestamp INTEGER NOT NULL,
+    interface TEXT NOT NULL,
+    received INTEGER NOT NULL,
+    send INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS interface_usage_by_time ON interface_usage (timestamp, interface);
+";
+
+/// Inserts the periods into an SQLite database from a background thread, the TUI never waits for the disk
+pub struct SqliteWriter {
+    sender: Option<Sender<UsagePeriod>>,
+    thread: Option<JoinHandle<()>>,
+    /// Failures of the background thread, reported at the next write
+    errors: Receiver<String>,
+}
+
+impl SqliteWriter {
+    pub fn open(path: &Path) -> Result<SqliteWriter, String> {
+        let connection = Connection::open(path)
+            .and_then(|connection| connection.execute_batch(SCHEMA).map(|_| connection))
+            .map_err(|err| format!("cannot open the export {}: {err}", path.display()))?;
+
+        let (sender, periods) = mpsc::channel();
+        let (errors_sender, errors) = mpsc::channel();
+        let thread = std::thread::spawn(move || write_periods(connection, periods, errors_sender));
+        Ok(SqliteWriter {
+            sender: Some(sender),
+            thread: Some(thread),
+            errors,
+        })
+    }
+}
+
+impl UsageWriter for SqliteWriter {
+    fn write(&mut self, period: &UsagePeriod) -> Result<(), String> {
+        if let Ok(error) = self.errors.try_recv() {
+            return Err(format!("export: {error}"));
+        }
+        self.sender
+            .as_ref()
+            .and_then(|sender| sender.send(period.clone()).ok())
+            .ok_or_else(|| "export: the writer stopped".to_string())
+    }
+}
+
+impl Drop for SqliteWriter {
+    /// Waits for the queued periods to be written
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn write_periods(
+    mut connection: Connection,
+    periods: Receiver<UsagePeriod>,
+    errors: Sender<String>,
+) {
+    while let Ok(period) = periods.recv() {
+        // the periods queued meanwhile are written in the same transaction
+        let batch: Vec<_> = std::iter::once(period).chain(periods.try_iter()).collect();
+        if let Err(error) = insert(&mut connection, &batch) {
+            let _ = errors.send(error.to_string());
+        }
+    }
+}
+
+fn insert(connection: &mut Connection, periods: &[UsagePeriod]) -> rusqlite::Result<()> {
+    let transaction = connection.transaction()?;
+    {
+        let mut insert_process = transaction.prepare_cached(
+            "INSERT INTO process_usage (timestamp, pid, comm, user, cgroup, received, send)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        let mut insert_interface = transaction.prepare_cached(
+            "INSERT INTO interface_usage (timestamp, interface, received, send)
+             VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for period in periods {
+            let timestamp = period.epoch_seconds();
+            for usage in &period.processes {
+                insert_process.execute(params![
+                    timestamp,
+                    usage.pid,
+                    usage.comm,
+                    usage.user,
+                    usage.cgroup,
+                    i64::from(usage.received),
+                    i64::from(usage.send)
+                ])?;
+            }
+            for usage in &period.interfaces {
+                insert_interface.execute(params![
+                    timestamp,
+                    usage.interface,
+                    i64::from(usage.received),
+                    i64::from(usage.send)
+                ])?;
+            }
+        }
+    }
+    transaction.commit()
+}
+
+#[cfg(test)]
+mod tests_sqlite {
+    use std::time::{Duration, SystemTime};
+
+    use rusqlite::Connection;
+
+    use crate::export::{InterfaceUsage, ProcessUsage, UsagePeriod, UsageWriter};
+
+    use super::SqliteWriter;
+
+    #[test]
+    fn test_periods_are_written_on_drop() {
+        let path = std::env::temp_dir().join(format!("net-monitor-{}.sqlite", std::process::id()));
+        let mut writer = SqliteWriter::open(&path).unwrap();
+        for second in [10, 20] {
+            writer
+                .write(&UsagePeriod {
+                    at: SystemTime::UNIX_EPOCH + Duration::from_secs(second),
+                    processes: vec![ProcessUsage {
+                        pid: 42,
+                        comm: "curl".to_string(),
+                        send: 100.into(),
+                        ..Default::default()
+                    }],
+                    interfaces: vec![InterfaceUsage {
+                        interface: "eth0".to_string(),
+                        received: 500.into(),
+                        send: 0.into(),
+                    }],
+                })
+                .unwrap();
+        }
+        drop(writer);
+
+        let connection = Connection::open(&path).unwrap();
+        let (rows, send): (i64, i64) = connection
+            .query_row(
+                "SELECT COUNT(*), SUM(send) FROM process_usage WHERE comm = 'curl'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        let last: i64 = connection
+            .query_row("SELECT MAX(timestamp) FROM interface_usage", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((rows, send), (2, 200));
+        assert_eq!(last, 20);
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use clap::ValueEnum;
+
+use crate::bandwidth_tracker::bytes::parse_amount;
+
+use super::{UsagePeriod, UsageWriter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One row per process or interface and period, after a header
+    Csv,
+    /// InfluxDB line protocol, tagged with the pid, comm, user, cgroup or interface
+    Influx,
+    /// One object per period
+    Json,
+}
+
+/// Columns of the CSV rows, the process ones are empty on the interface rows and the other way around
+const CSV_COLUMNS: &str = "timestamp,kind,pid,comm,user,cgroup,interface,received,send";
+
+pub struct StreamConfig {
+    pub format: Format,
+    /// Standard output when `None`
+    pub output: Option<PathBuf>,
+    /// The output file is rotated once it is this many bytes
+    pub rotate_size: Option<u64>,
+    /// Rotated files kept, as `<output>.1` (the latest) to `<output>.<rotate_keep>`
+    pub rotate_keep: usize,
+}
+
+/// Parses a size like `100MB` or `1GiB`
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    parse_amount(size).map(|bytes| i64::from(bytes) as u64)
+}
+
+/// Writes the periods as text rows to the standard output or to a rotating file
+pub struct StreamWriter {
+    format: Format,
+    output: Output,
+    /// The CSV header is written at the start of each file
+    header_pending: bool,
+}
+
+enum Output {
+    Stdout(io::Stdout),
+    File(RotatingFile),
+}
+
+impl StreamWriter {
+    pub fn open(config: &StreamConfig) -> Result<StreamWriter, String> {
+        let output = match &config.output {
+            Some(path) => Output::File(
+                RotatingFile::open(path, config.rotate_size, config.rotate_keep)
+                    .map_err(|err| format!("cannot open {}: {err}", path.display()))?,
+            ),
+            None => Output::Stdout(io::stdout()),
+        };
+        let header_pending = match &output {
+            Output::File(file) => file.size == 0,
+            Output::Stdout(_) => true,
+        };
+        Ok(StreamWriter {
+            format: config.format,
+            output,
+            header_pending,
+        })
+    }
+
+    fn try_write(&mut self, period: &UsagePeriod) -> io::Result<()> {
+        if let Output::File(file) = &mut self.output {
+            self.header_pending |= file.rotate_if_full()?;
+        }
+        let mut rows = Vec::new();
+        write_period(self.format, period, self.header_pending, &mut rows)?;
+        self.header_pending = false;
+        match &mut self.output {
+            Output::Stdout(stdout) => {
+                let mut stdout = stdout.lock();
+                stdout.write_all(&rows)?;
+                stdout.flush()
+            }
+            Output::File(file) => file.write_all(&rows),
+        }
+    }
+}
+
+impl UsageWriter for StreamWriter {
+    fn write(&mut self, period: &UsagePeriod) -> Result<(), String> {
+        self.try_write(period)
+            .map_err(|err| format!("export: {err}"))
+    }
+}
+
+fn write_period(
+    format: Format,
+    period: &UsagePeriod,
+    header: bool,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    match format {
+        Format::Csv => write_csv(period, header, out),
+        Format::Influx => write_influx(period, out),
+        Format::Json => {
+            serde_json::to_writer(&mut *out, period)?;
+            out.write_all(b"\n")
+        }
+    }
+}
+
+fn write_csv(period: &UsagePeriod, header: bool, out: &mut impl Write) -> io::Result<()> {
+    if header {
+        writeln!(out, "{CSV_COLUMNS}")?;
+    }
+    let timestamp = period.epoch_seconds();
+    for usage in &period.processes {
+        writeln!(
+            out,
+            "{timestamp},process,{},{},{},{},,{},{}",
+            usage.pid,
+            csv_field(&usage.comm),
+            csv_field(&usage.user),
+            csv_field(&usage.cgroup),
+            i64::from(usage.received),
+            i64::from(usage.send)
+        )?;
+    }
+    for usage in &period.interfaces {
+        writeln!(
+            out,
+            "{timestamp},interface,,,,,{},{},{}",
+            csv_field(&usage.interface),
+            i64::from(usage.received),
+            i64::from(usage.send)
+        )?;
+    }
+    Ok(())
+}
+
+/// Quoted when it holds a separator, a quote or a line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn write_influx(period: &UsagePeriod, out: &mut impl Write) -> io::Result<()> {
+    let timestamp = period
+        .at
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    for usage in &period.processes {
+        writeln!(
+            out,
+            "net_monitor_process{} received={}i,send={}i {timestamp}",
+            influx_tags(&[
+                ("pid", &usage.pid.to_string()),
+                ("comm", &usage.comm),
+                ("user", &usage.user),
+                ("cgroup", &usage.cgroup),
+            ]),
+            i64::from(usage.received),
+            i64::from(usage.send)
+        )?;
+    }
+    for usage in &period.interfaces {
+        writeln!(
+            out,
+            "net_monitor_interface{} received={}i,send={}i {timestamp}",
+            influx_tags(&[("interface", &usage.interface)]),
+            i64::from(usage.received),
+            i64::from(usage.send)
+        )?;
+    }
+    Ok(())
+}
+
+/// `,key=value` for each tag, the empty ones are left out as the line protocol does not allow them
+fn influx_tags(tags: &[(&str, &str)]) -> String {
+    tags.iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace(',', "\\,")
+                .replace('=', "\\=")
+                .replace(' ', "\\ ");
+            format!(",{key}={value}")
+        })
+        .collect()
+}
+
+/// Appends to a file, moved to `<path>.1` once it is full, the older ones being shifted up to `<path>.<keep>`
+struct RotatingFile {
+    path: PathBuf,
+    max_size: Option<u64>,
+    keep: usize,
+    file: BufWriter<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_size: Option<u64>, keep: usize) -> io::Result<RotatingFile> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(RotatingFile {
+            path: path.to_path_buf(),
+            max_size,
+            keep,
+            size: file.metadata()?.len(),
+            file: BufWriter::new(file),
+        })
+    }
+
+    /// Returns whether the file was rotated, the next rows start a new file
+    fn rotate_if_full(&mut self) -> io::Result<bool> {
+        if self.max_size.is_none_or(|max_size| self.size < max_size) {
+            return Ok(false);
+        }
+        self.file.flush()?;
+        for index in (1..self.keep).rev() {
+            match fs::rename(self.rotated(index), self.rotated(index + 1)) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+        if self.keep > 0 {
+            fs::rename(&self.path, self.rotated(1))?;
+        }
+        let file = File::create(&self.path)?;
+        self.file = BufWriter::new(file);
+        self.size = 0;
+        Ok(true)
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut path = OsString::from(&self.path);
+        path.push(format!(".{index}"));
+        PathBuf::from(path)
+    }
+
+    fn write_all(&mut self, rows: &[u8]) -> io::Result<()> {
+        self.file.write_all(rows)?;
+        self.file.flush()?;
+        self.size += rows.len() as u64;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests_stream {
+    use std::time::{Duration, SystemTime};
+
+    use crate::export::{InterfaceUsage, ProcessUsage, UsagePeriod, UsageWriter};
+
+    use super::{write_period, Format, StreamConfig, StreamWriter};
+
+    fn period() -> UsagePeriod {
+        UsagePeriod {
+            at: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            processes: vec![ProcessUsage {
+                pid: 42,
+                comm: "web, content".to_string(),
+                user: "alice".to_string(),
+                cgroup: String::new(),
+                received: 100.into(),
+                send: 200.into(),
+            }],
+            interfaces: vec![InterfaceUsage {
+                interface: "eth0".to_string(),
+                received: 300.into(),
+                send: 400.into(),
+            }],
+        }
+    }
+
+    fn written(format: Format, header: bool) -> String {
+        let mut out = Vec::new();
+        write_period(format, &period(), header, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        assert_eq!(
+            written(Format::Csv, true),
+            "timestamp,kind,pid,comm,user,cgroup,interface,received,send\n\
+             1700000000,process,42,\"web, content\",alice,,,100,200\n\
+             1700000000,interface,,,,,eth0,300,400\n"
+        );
+    }
+
+    #[test]
+    fn test_influx_lines() {
+        assert_eq!(
+            written(Format::Influx, false),
+            "net_monitor_process,pid=42,comm=web\\,\\ content,user=alice received=100i,send=200i 1700000000000000000\n\
+             net_monitor_interface,interface=eth0 received=300i,send=400i 1700000000000000000\n"
+        );
+    }
+
+    #[test]
+    fn test_rotation_starts_a_new_csv() {
+        let path = std::env::temp_dir().join(format!("net-monitor-{}.csv", std::process::id()));
+        let mut writer = StreamWriter::open(&StreamConfig {
+            format: Format::Csv,
+            output: Some(path.clone()),
+            rotate_size: Some(100),
+            rotate_keep: 1,
+        })
+        .unwrap();
+        for _ in 0..3 {
+            writer.write(&period()).unwrap();
+        }
+        drop(writer);
+
+        let rotated = path.with_extension("csv.1");
+        let current = std::fs::read_to_string(&path).unwrap();
+        let previous = std::fs::read_to_string(&rotated).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&rotated).unwrap();
+        assert_eq!(current.lines().count(), 3);
+        assert_eq!(previous.lines().count(), 3);
+        assert!(previous.starts_with("timestamp,"));
+    }
+}
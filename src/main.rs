@@ -1,52 +1,245 @@
+use bandwidth_tracker::{
+    proc_source::ProcSource,
+    recording::{Recorder, Replay},
+    sock_diag_source::SockDiagSource,
+    source::{CounterSource, ProbeSource, SourceKind},
+};
 use bpf::probs::LoadedProb;
+use clap::Parser;
+use config::{cli::Cli, Config};
 use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
+use export::UsageExport;
 use libbpf_rs::skel::{OpenSkel, SkelBuilder};
 use ratatui::{
     prelude::{Backend, CrosstermBackend},
     Terminal,
 };
+use report::{Summary, SummaryFormat};
 use std::{
     error::Error,
     io::{self, stdout},
     mem::MaybeUninit,
-    time::Duration,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
 };
 use tui::{events::Event, render::draw_state, state::Model};
 
 mod bandwidth_tracker;
 mod bpf;
+mod config;
+mod export;
 #[path = "bpf/.output/packet_size.skel.rs"]
 mod packet_size;
+mod report;
+mod system;
 mod tui;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    init_panic_hook();
+    let cli = Cli::parse();
+    let config = Config::load(&cli)?;
+
+    if let Some(from) = cli.history_at {
+        return report::print_history(&config, from, cli.history_span);
+    }
+
+    if let Some(path) = &cli.replay {
+        return run(
+            Box::new(Replay::open(path, cli.replay_speed)?),
+            config,
+            &cli,
+            None,
+        );
+    }
+
+    match cli.source {
+        SourceKind::Proc => return run_live(ProcSource::new(), config, &cli, None),
+        SourceKind::SockDiag => {
+            let source = SockDiagSource::open().map_err(|err| format!("sock_diag: {err}"))?;
+            return run_live(source, config, &cli, None);
+        }
+        SourceKind::Probes => {}
+    }
+
     let mut open_object = MaybeUninit::uninit();
-    let opened_skel = packet_size::PacketSizeSkelBuilder::default().open(&mut open_object)?;
-    let mut skel = opened_skel.load()?;
-    let _probs = LoadedProb::load_ebpf_monitoring_probs(&mut skel)?;
+    let probes = packet_size::PacketSizeSkelBuilder::default()
+        .open(&mut open_object)
+        .and_then(|opened_skel| opened_skel.load())
+        .and_then(|mut skel| Ok((LoadedProb::load_ebpf_monitoring_probs(&mut skel)?, skel)));
+    let (_probs, skel) = match probes {
+        Ok(probes) => probes,
+        Err(error) => {
+            if let Ok(source) = SockDiagSource::open() {
+                let notice = format!(
+                    "cannot load the probes ({error}), counting the TCP traffic with sock_diag"
+                );
+                return run_live(source, config, &cli, Some(notice));
+            }
+            let notice =
+                format!("cannot load the probes ({error}), estimating the traffic from /proc");
+            return run_live(ProcSource::new(), config, &cli, Some(notice));
+        }
+    };
 
     let map_collection = skel.maps;
     let packet_stats = map_collection.packet_stats;
+    run_live(ProbeSource::new(&packet_stats), config, &cli, None)
+}
+
+/// Monitors this host, recording the session with `--record`
+fn run_live<'a>(
+    source: impl CounterSource + 'a,
+    config: Config,
+    cli: &Cli,
+    notice: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    match &cli.record {
+        Some(path) => run(
+            Box::new(Recorder::create(path, source)?),
+            config,
+            cli,
+            notice,
+        ),
+        None => run(Box::new(source), config, cli, notice),
+    }
+}
+
+/// Shows the TUI, or samples without it with `--duration`, `--once` or `--format`
+///
+/// The `notice`, like why the probes could not be used, goes to the status line and the log of the TUI, or
+/// to the standard error without it.
+fn run(
+    source: Box<dyn CounterSource + '_>,
+    config: Config,
+    cli: &Cli,
+    notice: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    let duration = cli.duration.or(cli.once.then_some(Duration::from_secs(1)));
+    if config.export.stream.is_some() || duration.is_some() {
+        if let Some(notice) = notice.as_deref() {
+            eprintln!("{notice}");
+        }
+    }
+    if config.export.stream.is_some() {
+        return run_headless(source, config, duration, true, |_| {});
+    }
+    if let Some(duration) = duration {
+        let units = config.display.unit_format()?;
+        // every tick is a period of the summary
+        let mut ticks = UsageExport::new(Duration::ZERO);
+        let mut summary = Summary::default();
+        // a summary for scripts leaves the long-term history and the usage of the quotas as they are
+        run_headless(source, config, Some(duration), false, |model| {
+            let period = ticks.add_tick(&model.bandwidth_tracker, |pid| {
+                model.process_identity(pid).cloned()
+            });
+            if let Some(period) = period {
+                summary.add(&period);
+            }
+        })?;
+        match cli.summary {
+            SummaryFormat::Table => summary.print(cli.top, &units),
+            SummaryFormat::Json => println!("{}", summary.to_json(cli.top)),
+        }
+        return Ok(());
+    }
 
     let refresh_rate = Duration::from_millis(160);
-    let mut state_model = Model::init(&packet_stats, refresh_rate)?;
+    let mut state_model = Model::init(source, refresh_rate, config)?;
+    if let Some(notice) = notice {
+        state_model.report(notice);
+    }
+    init_panic_hook();
     let mut terminal = init_tui()?;
+    let mut last_tick = Instant::now();
+
+    loop {
+        terminal.draw(|frame| draw_state(frame, &mut state_model))?;
+
+        let timeout = state_model.refresh_rate.saturating_sub(last_tick.elapsed());
+        if crossterm::event::poll(timeout)? {
+            let event = crossterm::event::read()?;
+            let event = if state_model.is_editing_filter() {
+                Event::from_text_input(event)
+            } else {
+                state_model.keymap.action_for(event)
+            };
+            match event {
+                Ok(Event::Quit) => break,
+                Ok(event) => state_model = state_model.handel_event(&event)?,
+                Err(_) => {}
+            }
+        }
+
+        if last_tick.elapsed() >= state_model.refresh_rate {
+            state_model = state_model.handel_event(&Event::Tick)?;
+            last_tick = Instant::now();
+        }
+    }
+
+    restore_tui()?;
+    state_model.accounting.save()?;
+    Ok(())
+}
+
+/// Set by SIGINT and SIGTERM, to write what is pending before exiting
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_stop(_signal: libc::c_int) {
+    STOPPING.store(true, Ordering::Relaxed);
+}
+
+/// Ticks the model without drawing it, for the `duration` or until interrupted
+///
+/// Without `keep_state`, nothing is written to the long-term history nor to the usage of the quotas.
+fn run_headless<'a>(
+    source: Box<dyn CounterSource + 'a>,
+    mut config: Config,
+    duration: Option<Duration>,
+    keep_state: bool,
+    mut on_tick: impl FnMut(&Model<'a>),
+) -> Result<(), Box<dyn Error>> {
+    let refresh_rate = Duration::from_secs(1);
+    if !keep_state {
+        config.history.enabled = false;
+    }
+    let mut state_model = Model::init(source, refresh_rate, config)?;
+    if !keep_state {
+        state_model.accounting.keep_in_memory();
+    }
+    if state_model.is_approximate() {
+        eprintln!("the traffic of the processes is approximate, estimated from /proc");
+    }
+    let handler = request_stop as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: the handler only stores to an atomic
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
 
+    let started = Instant::now();
     loop {
-        terminal.draw(|frame| draw_state(frame, &state_model))?;
-        if crossterm::event::poll(state_model.refresh_rate)?
-            && Event::try_from(crossterm::event::read()?) == Ok(Event::Quit)
+        state_model = state_model.handel_event(&Event::Tick)?;
+        on_tick(&state_model);
+        if let Some(error) = state_model.take_export_error() {
+            return Err(error.into());
+        }
+        if let Some(message) = state_model.status_message.take() {
+            eprintln!("{message}");
+        }
+
+        let elapsed = started.elapsed();
+        if STOPPING.load(Ordering::Relaxed) || duration.is_some_and(|duration| elapsed >= duration)
         {
             break;
         }
-        state_model = state_model.handel_event(&Event::Tick)?;
+        std::thread::sleep(state_model.refresh_rate);
     }
 
-    restore_tui()?;
+    state_model.accounting.save()?;
     Ok(())
 }
 
@@ -62,11 +255,13 @@ pub fn init_panic_hook() {
 pub fn init_tui() -> io::Result<Terminal<impl Backend>> {
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableMouseCapture)?;
     Terminal::new(CrosstermBackend::new(stdout()))
 }
 
 pub fn restore_tui() -> io::Result<()> {
     disable_raw_mode()?;
+    stdout().execute(DisableMouseCapture)?;
     stdout().execute(LeaveAlternateScreen)?;
     Ok(())
 }
@@ -0,0 +1,317 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Days, Local, NaiveDateTime, NaiveTime, TimeZone};
+use clap::ValueEnum;
+use serde_json::json;
+
+use crate::{
+    bandwidth_tracker::{
+        bytes::{BytesPerSecond, NumberOfBytes, UnitFormat},
+        store::{HistoryStore, Kind},
+    },
+    config::Config,
+    export::UsagePeriod,
+};
+
+/// Prints the programs and interfaces that used the network in the `span` from `from`
+pub fn print_history(
+    config: &Config,
+    from: SystemTime,
+    span: Duration,
+) -> Result<(), Box<dyn Error>> {
+    let units = config.display.unit_format()?;
+    let store = HistoryStore::open(&config.history)?.ok_or("the history is disabled")?;
+    let to = from + span;
+
+    println!(
+        "{} to {}",
+        DateTime::<Local>::from(from).format("%Y-%m-%d %H:%M:%S"),
+        DateTime::<Local>::from(to).format("%Y-%m-%d %H:%M:%S")
+    );
+    for (kind, title) in [(Kind::Program, "program"), (Kind::Interface, "interface")] {
+        let rows = store.top(kind, from, to)?;
+        let width = rows
+            .iter()
+            .map(|(name, _, _)| name.len())
+            .chain([title.len()])
+            .max()
+            .unwrap_or_default();
+        println!();
+        println!("{title:<width$}  {:>12}  {:>12}", "received", "send");
+        for (name, received, send) in rows {
+            println!(
+                "{name:<width$}  {:>12}  {:>12}",
+                units.bytes(received),
+                units.bytes(send)
+            );
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct SummaryRow {
+    comm: String,
+    user: String,
+    received: NumberOfBytes,
+    send: NumberOfBytes,
+    peak_received: BytesPerSecond,
+    peak_send: BytesPerSecond,
+}
+
+impl SummaryRow {
+    fn add(&mut self, received: NumberOfBytes, send: NumberOfBytes, elapsed: Option<Duration>) {
+        self.received = self.received + received;
+        self.send = self.send + send;
+        if let Some(elapsed) = elapsed {
+            self.peak_received = self
+                .peak_received
+                .max(BytesPerSecond::new(received, elapsed));
+            self.peak_send = self.peak_send.max(BytesPerSecond::new(send, elapsed));
+        }
+    }
+
+    fn total(&self) -> NumberOfBytes {
+        self.received + self.send
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SummaryFormat {
+    /// Aligned columns with units
+    #[default]
+    Table,
+    /// One object, in bytes and bytes per second
+    Json,
+}
+
+/// Totals and peak rates of the processes and interfaces over a sampling, fed one period per tick
+#[derive(Default)]
+pub struct Summary {
+    started: Option<SystemTime>,
+    last_period: Option<SystemTime>,
+    processes: HashMap<i32, SummaryRow>,
+    interfaces: BTreeMap<String, SummaryRow>,
+}
+
+impl Summary {
+    pub fn add(&mut self, period: &UsagePeriod) {
+        self.started.get_or_insert(period.at);
+        // the peaks are measured over the periods, the first one has no length
+        let elapsed = self
+            .last_period
+            .and_then(|last| period.at.duration_since(last).ok())
+            .filter(|elapsed| !elapsed.is_zero());
+        self.last_period = Some(period.at);
+
+        for usage in &period.processes {
+            let row = self.processes.entry(usage.pid).or_default();
+            if row.comm.is_empty() {
+                row.comm = usage.comm.clone();
+                row.user = usage.user.clone();
+            }
+            row.add(usage.received, usage.send, elapsed);
+        }
+        for usage in &period.interfaces {
+            let row = self.interfaces.entry(usage.interface.clone()).or_default();
+            row.add(usage.received, usage.send, elapsed);
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        match (self.started, self.last_period) {
+            (Some(started), Some(last)) => last.duration_since(started).unwrap_or_default(),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// The `count` processes that exchanged the most, the most first
+    fn top_processes(&self, count: usize) -> Vec<(i32, &SummaryRow)> {
+        let mut processes: Vec<_> = self
+            .processes
+            .iter()
+            .map(|(pid, row)| (*pid, row))
+            .collect();
+        processes
+            .sort_by(|(pid_a, a), (pid_b, b)| b.total().cmp(&a.total()).then(pid_a.cmp(pid_b)));
+        processes.truncate(count);
+        processes
+    }
+
+    pub fn print(&self, top: usize, units: &UnitFormat) {
+        let seconds = Duration::from_secs(self.duration().as_secs_f64().round() as u64);
+        println!("sampled for {}", humantime::format_duration(seconds));
+        println!();
+        println!(
+            "{:>7}  {:<16}  {:<10}  {:>12}  {:>12}  {:>14}  {:>14}",
+            "pid", "program", "user", "received", "send", "peak received", "peak send"
+        );
+        for (pid, row) in self.top_processes(top) {
+            println!(
+                "{pid:>7}  {:<16}  {:<10}  {:>12}  {:>12}  {:>14}  {:>14}",
+                row.comm,
+                row.user,
+                units.bytes(row.received),
+                units.bytes(row.send),
+                units.rate(row.peak_received),
+                units.rate(row.peak_send)
+            );
+        }
+
+        let width = self
+            .interfaces
+            .keys()
+            .map(String::len)
+            .chain(["interface".len()])
+            .max()
+            .unwrap_or_default();
+        println!();
+        println!(
+            "{:<width$}  {:>12}  {:>12}  {:>14}  {:>14}",
+            "interface", "received", "send", "peak received", "peak send"
+        );
+        for (interface, row) in &self.interfaces {
+            println!(
+                "{interface:<width$}  {:>12}  {:>12}  {:>14}  {:>14}",
+                units.bytes(row.received),
+                units.bytes(row.send),
+                units.rate(row.peak_received),
+                units.rate(row.peak_send)
+            );
+        }
+    }
+
+    /// Bytes and bytes per second, without units
+    pub fn to_json(&self, top: usize) -> serde_json::Value {
+        let processes: Vec<_> = self
+            .top_processes(top)
+            .into_iter()
+            .map(|(pid, row)| {
+                json!({
+                    "pid": pid,
+                    "comm": row.comm,
+                    "user": row.user,
+                    "received": row.received,
+                    "send": row.send,
+                    "peak_received_per_second": f64::from(row.peak_received),
+                    "peak_send_per_second": f64::from(row.peak_send),
+                })
+            })
+            .collect();
+        let interfaces: Vec<_> = self
+            .interfaces
+            .iter()
+            .map(|(interface, row)| {
+                json!({
+                    "interface": interface,
+                    "received": row.received,
+                    "send": row.send,
+                    "peak_received_per_second": f64::from(row.peak_received),
+                    "peak_send_per_second": f64::from(row.peak_send),
+                })
+            })
+            .collect();
+        json!({
+            "duration_seconds": self.duration().as_secs_f64(),
+            "processes": processes,
+            "interfaces": interfaces,
+        })
+    }
+}
+
+/// Parses a local time, `HH:MM` being its last occurrence before now
+pub fn parse_time(time: &str) -> Result<SystemTime, String> {
+    parse_time_before(time, Local::now().naive_local())
+}
+
+fn parse_time_before(time: &str, now: NaiveDateTime) -> Result<SystemTime, String> {
+    let invalid = || format!("invalid time `{time}`, expected `HH:MM` or `YYYY-MM-DD HH:MM`");
+    let at = match NaiveTime::parse_from_str(time, "%H:%M") {
+        Ok(at) if now.date().and_time(at) <= now => now.date().and_time(at),
+        Ok(at) => (now.date() - Days::new(1)).and_time(at),
+        Err(_) => NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").map_err(|_| invalid())?,
+    };
+    Local
+        .from_local_datetime(&at)
+        .earliest()
+        .map(SystemTime::from)
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests_report {
+    use std::time::{Duration, SystemTime};
+
+    use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+
+    use crate::export::{InterfaceUsage, ProcessUsage, UsagePeriod};
+
+    use super::{parse_time_before, Summary};
+
+    fn local(date: (i32, u32, u32), hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_hour_is_in_the_past() {
+        let now = local((2024, 3, 10), 9, 30);
+        let expected =
+            |at: NaiveDateTime| Local.from_local_datetime(&at).earliest().unwrap().into();
+
+        assert_eq!(
+            parse_time_before("03:00", now),
+            Ok(expected(local((2024, 3, 10), 3, 0)))
+        );
+        assert_eq!(
+            parse_time_before("23:15", now),
+            Ok(expected(local((2024, 3, 9), 23, 15)))
+        );
+        assert_eq!(
+            parse_time_before("2024-01-02 03:04", now),
+            Ok(expected(local((2024, 1, 2), 3, 4)))
+        );
+        assert!(parse_time_before("3 am", now).is_err());
+    }
+
+    #[test]
+    fn test_summary_ranks_the_processes() {
+        let period = |second: u64, bytes: i32| UsagePeriod {
+            at: SystemTime::UNIX_EPOCH + Duration::from_secs(second),
+            processes: [(1, bytes), (2, 2 * bytes), (3, 100)]
+                .into_iter()
+                .map(|(pid, send)| ProcessUsage {
+                    pid,
+                    send: send.into(),
+                    ..Default::default()
+                })
+                .collect(),
+            interfaces: vec![InterfaceUsage {
+                interface: "eth0".to_string(),
+                received: bytes.into(),
+                send: 0.into(),
+            }],
+        };
+        let mut summary = Summary::default();
+        for (second, bytes) in [(0, 10), (1, 1000), (3, 4000)] {
+            summary.add(&period(second, bytes));
+        }
+
+        let top: Vec<_> = summary
+            .top_processes(2)
+            .into_iter()
+            .map(|(pid, _)| pid)
+            .collect();
+        assert_eq!(top, [2, 1]);
+        let json = summary.to_json(2);
+        assert_eq!(json["duration_seconds"], 3.);
+        assert_eq!(json["processes"][1]["send"], 5010);
+        assert_eq!(json["interfaces"][0]["peak_received_per_second"], 2000.);
+    }
+}
@@ -0,0 +1,40 @@
+use std::{fs, str::FromStr};
+
+/// Link properties of a network interface, read from `/sys/class/net/<interface>`
+#[derive(Clone, Default)]
+pub struct LinkInfo {
+    /// RFC 2863 operational state: `up`, `down`, `unknown`...
+    pub operstate: String,
+    pub mtu: Option<u32>,
+    /// In Mbit/s, `None` for virtual interfaces or when the link is down
+    pub speed: Option<u32>,
+    /// Number of times the link went up or down, a high value is a flapping NIC
+    pub carrier_changes: Option<u64>,
+}
+
+fn read_attribute<T: FromStr>(interface: &str, attribute: &str) -> Option<T> {
+    fs::read_to_string(format!("/sys/class/net/{interface}/{attribute}"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+impl LinkInfo {
+    pub fn read(interface: &str) -> LinkInfo {
+        LinkInfo {
+            operstate: read_attribute(interface, "operstate").unwrap_or_default(),
+            mtu: read_attribute(interface, "mtu"),
+            // the kernel reports -1 when the speed is unknown, which fails to parse as u32
+            speed: read_attribute(interface, "speed"),
+            carrier_changes: read_attribute(interface, "carrier_changes"),
+        }
+    }
+
+    pub fn is_down(&self) -> bool {
+        matches!(
+            self.operstate.as_str(),
+            "down" | "lowerlayerdown" | "notpresent"
+        )
+    }
+}
@@ -0,0 +1,4 @@
+pub mod link;
+pub mod process;
+pub mod sock_diag;
+pub mod sockets;
@@ -0,0 +1,169 @@
+use std::{io, path::PathBuf};
+
+use procfs::{process::Process, WithCurrentSystemInfo};
+use serde::{Deserialize, Serialize};
+
+use super::sockets::{sockets_of_process, Socket};
+
+/// What `/proc/<pid>` tells about a process, for the detail view
+pub struct ProcessDetails {
+    pub pid: i32,
+    pub name: String,
+    pub cmdline: String,
+    pub exe: Option<PathBuf>,
+    pub user: String,
+    pub cgroups: Vec<String>,
+    pub start_time: Option<String>,
+    pub sockets: Vec<Socket>,
+}
+
+/// What identifies a process in the tables, kept around once the process is gone
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProcessIdentity {
+    pub name: String,
+    pub cmdline: String,
+    pub user: String,
+    /// Path of the process in the unified (v2) hierarchy, or in the first one
+    #[serde(default)]
+    pub cgroup: String,
+}
+
+impl ProcessIdentity {
+    pub fn read(process: &Process) -> Option<ProcessIdentity> {
+        Some(ProcessIdentity {
+            name: process.stat().ok()?.comm,
+            cmdline: process
+                .cmdline()
+                .map(|arguments| arguments.join(" "))
+                .unwrap_or_default(),
+            user: process.uid().map(user_name).unwrap_or_default(),
+            cgroup: process
+                .cgroups()
+                .ok()
+                .and_then(|cgroups| {
+                    let mut cgroups = cgroups.0;
+                    let unified = cgroups.iter().position(|cgroup| cgroup.hierarchy == 0);
+                    (!cgroups.is_empty()).then(|| cgroups.swap_remove(unified.unwrap_or_default()))
+                })
+                .map(|cgroup| cgroup.pathname)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Name of the user, or the uid when it has no entry in the user database
+pub fn user_name(uid: u32) -> String {
+    uzers::get_user_by_uid(uid)
+        .map(|user| user.name().to_string_lossy().into_owned())
+        .unwrap_or_else(|| uid.to_string())
+}
+
+impl ProcessDetails {
+    pub fn read(process: &Process) -> ProcessDetails {
+        let stat = process.stat().ok();
+
+        ProcessDetails {
+            pid: process.pid,
+            name: stat
+                .as_ref()
+                .map(|stat| stat.comm.clone())
+                .unwrap_or_default(),
+            cmdline: process
+                .cmdline()
+                .map(|arguments| arguments.join(" "))
+                .unwrap_or_default(),
+            exe: process.exe().ok(),
+            user: process.uid().map(user_name).unwrap_or_default(),
+            cgroups: process
+                .cgroups()
+                .map(|cgroups| cgroups.into_iter().map(|cgroup| cgroup.pathname).collect())
+                .unwrap_or_default(),
+            start_time: stat
+                .and_then(|stat| stat.starttime().get().ok())
+                .map(|start_time| start_time.format("%Y-%m-%d %H:%M:%S").to_string()),
+            sockets: sockets_of_process(process),
+        }
+    }
+}
+
+/// Signals offered to be sent from the TUI, the most useful first
+pub const SIGNALS: [(&str, i32); 10] = [
+    ("SIGTERM", libc::SIGTERM),
+    ("SIGKILL", libc::SIGKILL),
+    ("SIGINT", libc::SIGINT),
+    ("SIGHUP", libc::SIGHUP),
+    ("SIGQUIT", libc::SIGQUIT),
+    ("SIGSTOP", libc::SIGSTOP),
+    ("SIGCONT", libc::SIGCONT),
+    ("SIGUSR1", libc::SIGUSR1),
+    ("SIGUSR2", libc::SIGUSR2),
+    ("SIGWINCH", libc::SIGWINCH),
+];
+
+/// Start time of the process in clock ticks since boot, `None` once it exited
+///
+/// A pid can be reused by another process, the pid and the start time together identify a process.
+pub fn start_time_of(pid: i32) -> Option<u64> {
+    let stat = Process::new(pid).ok()?.stat().ok()?;
+    Some(stat.starttime)
+}
+
+pub fn send_signal(pid: i32, signal: i32) -> io::Result<()> {
+    // no memory is shared with the callee, only plain integers
+    if unsafe { libc::kill(pid, signal) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Change the niceness of the process, from -20 (highest priority) to 19
+pub fn renice(pid: i32, nice: i32) -> io::Result<()> {
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests_process {
+    use std::net::TcpListener;
+
+    use procfs::process::Process;
+
+    use crate::system::sockets::Protocol;
+
+    use super::{start_time_of, user_name, ProcessDetails};
+
+    #[test]
+    fn test_details_of_this_process() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let details = ProcessDetails::read(&Process::myself().unwrap());
+
+        assert_eq!(details.pid, std::process::id() as i32);
+        assert!(!details.name.is_empty());
+        assert!(!details.cmdline.is_empty());
+        assert_eq!(details.exe, std::env::current_exe().ok());
+        assert_eq!(details.user, user_name(unsafe { libc::getuid() }));
+        assert!(!details.cgroups.is_empty());
+        assert!(details.start_time.is_some());
+        assert!(details.sockets.iter().any(|socket| {
+            socket.protocol == Protocol::Tcp && socket.local == listener.local_addr().unwrap()
+        }));
+    }
+
+    #[test]
+    fn test_start_time_is_gone_once_exited() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("10")
+            .spawn()
+            .unwrap();
+        let pid = child.id() as i32;
+        let start_time = start_time_of(pid);
+        assert!(start_time.is_some());
+        assert_eq!(start_time_of(pid), start_time);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_eq!(start_time_of(pid), None);
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+/// `SOCK_DIAG_BY_FAMILY` from `linux/sock_diag.h`
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+/// `INET_DIAG_INFO` attribute of `linux/inet_diag.h`, holding a `struct tcp_info`
+const INET_DIAG_INFO: u16 = 2;
+
+const NLMSG_HEADER_LEN: usize = 16;
+/// `struct inet_diag_req_v2`
+const REQUEST_LEN: usize = 56;
+/// `struct inet_diag_msg`
+const DIAG_MSG_LEN: usize = 72;
+const UID_OFFSET: usize = 64;
+const INODE_OFFSET: usize = 68;
+/// Offsets of `tcpi_bytes_acked` and `tcpi_bytes_received` in `struct tcp_info`, since Linux 4.2
+const BYTES_ACKED_OFFSET: usize = 120;
+const BYTES_RECEIVED_OFFSET: usize = 128;
+
+/// Byte counters of a TCP socket, for the whole life of the socket
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TcpCounters {
+    pub inode: u64,
+    pub uid: u32,
+    pub bytes_received: u64,
+    /// Bytes sent and acknowledged by the peer, plus one for the SYN of the connections opened by this side
+    pub bytes_acked: u64,
+}
+
+/// Counters of every TCP socket of this network namespace, from a netlink `sock_diag` dump
+///
+/// No privileges are needed, the sockets of every user are listed.
+pub fn tcp_sockets() -> io::Result<Vec<TcpCounters>> {
+    let mut sockets = dump(libc::AF_INET as u8)?;
+    sockets.extend(dump(libc::AF_INET6 as u8)?);
+    Ok(sockets)
+}
+
+fn dump(family: u8) -> io::Result<Vec<TcpCounters>> {
+    // SAFETY: a new socket, owned by the returned fd
+    let socket = unsafe {
+        let fd = libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            libc::NETLINK_SOCK_DIAG,
+        );
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        OwnedFd::from_raw_fd(fd)
+    };
+
+    let request = dump_request(family);
+    // SAFETY: the kernel address and the request outlive the call
+    let sent = unsafe {
+        let mut kernel: libc::sockaddr_nl = std::mem::zeroed();
+        kernel.nl_family = libc::AF_NETLINK as u16;
+        libc::sendto(
+            socket.as_raw_fd(),
+            request.as_ptr().cast(),
+            request.len(),
+            0,
+            (&kernel as *const libc::sockaddr_nl).cast(),
+            std::mem::size_of::<libc::sockaddr_nl>() as u32,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut sockets = Vec::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        // SAFETY: the kernel writes at most the length of the buffer
+        let received = unsafe {
+            libc::recv(
+                socket.as_raw_fd(),
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                0,
+            )
+        };
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if parse_messages(&buffer[..received as usize], &mut sockets)? {
+            return Ok(sockets);
+        }
+    }
+}
+
+/// `nlmsghdr` followed by an `inet_diag_req_v2` asking for the `tcp_info` of the sockets in any state
+fn dump_request(family: u8) -> Vec<u8> {
+    let mut request = Vec::with_capacity(NLMSG_HEADER_LEN + REQUEST_LEN);
+    request.extend(((NLMSG_HEADER_LEN + REQUEST_LEN) as u32).to_ne_bytes());
+    request.extend(SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    request.extend(((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+    // sequence number and port id
+    request.extend([0; 8]);
+
+    request.extend([
+        family,
+        libc::IPPROTO_TCP as u8,
+        1 << (INET_DIAG_INFO - 1),
+        0,
+    ]);
+    // every state
+    request.extend(u32::MAX.to_ne_bytes());
+    // no filter on the addresses, the interface nor the cookie
+    request.resize(NLMSG_HEADER_LEN + REQUEST_LEN, 0);
+    request
+}
+
+/// Adds the sockets of a datagram of the dump, returns whether it was the last one
+fn parse_messages(mut datagram: &[u8], sockets: &mut Vec<TcpCounters>) -> io::Result<bool> {
+    while datagram.len() >= NLMSG_HEADER_LEN {
+        let length = read_u32(datagram, 0) as usize;
+        let kind = read_u16(datagram, 4);
+        if length < NLMSG_HEADER_LEN || length > datagram.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated sock_diag message",
+            ));
+        }
+        let payload = &datagram[NLMSG_HEADER_LEN..length];
+        match kind as i32 {
+            libc::NLMSG_DONE => return Ok(true),
+            libc::NLMSG_ERROR => {
+                let errno = payload.get(..4).map(|_| read_u32(payload, 0) as i32);
+                return Err(io::Error::from_raw_os_error(-errno.unwrap_or(-libc::EIO)));
+            }
+            _ if payload.len() >= DIAG_MSG_LEN => sockets.extend(parse_socket(payload)),
+            _ => {}
+        }
+        datagram = &datagram[align(length).min(datagram.len())..];
+    }
+    Ok(false)
+}
+
+/// `inet_diag_msg` followed by its attributes, `None` for the sockets without `tcp_info` like the time-wait ones
+fn parse_socket(payload: &[u8]) -> Option<TcpCounters> {
+    let mut attributes = &payload[DIAG_MSG_LEN..];
+    while attributes.len() >= 4 {
+        let length = read_u16(attributes, 0) as usize;
+        let kind = read_u16(attributes, 2);
+        if length < 4 || length > attributes.len() {
+            return None;
+        }
+        let info = &attributes[4..length];
+        if kind == INET_DIAG_INFO && info.len() >= BYTES_RECEIVED_OFFSET + 8 {
+            return Some(TcpCounters {
+                inode: u64::from(read_u32(payload, INODE_OFFSET)),
+                uid: read_u32(payload, UID_OFFSET),
+                bytes_received: read_u64(info, BYTES_RECEIVED_OFFSET),
+                bytes_acked: read_u64(info, BYTES_ACKED_OFFSET),
+            });
+        }
+        attributes = &attributes[align(length).min(attributes.len())..];
+    }
+    None
+}
+
+/// Netlink messages and attributes are aligned on 4 bytes
+fn align(length: usize) -> usize {
+    length.next_multiple_of(4)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_ne_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap_or_default())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_ne_bytes(bytes[offset..offset + 8].try_into().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests_sock_diag {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        os::{fd::AsRawFd, unix::fs::MetadataExt},
+    };
+
+    use super::tcp_sockets;
+
+    fn inode(stream: &TcpStream) -> u64 {
+        std::fs::metadata(format!("/proc/self/fd/{}", stream.as_raw_fd()))
+            .unwrap()
+            .ino()
+    }
+
+    #[test]
+    fn test_loopback_connection_is_counted() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server, _) = listener.accept().unwrap();
+
+        client.write_all(&[42; 10_000]).unwrap();
+        let mut received = vec![0; 10_000];
+        server.read_exact(&mut received).unwrap();
+        server.write_all(b"ok").unwrap();
+        client.read_exact(&mut [0; 2]).unwrap();
+
+        let (client, server) = (inode(&client), inode(&server));
+        let counters = || {
+            let sockets = tcp_sockets().unwrap();
+            let find = |inode| *sockets.iter().find(|socket| socket.inode == inode).unwrap();
+            (find(client), find(server))
+        };
+        // the acknowledgements may be delayed
+        let (mut client, mut server) = counters();
+        for _ in 0..50 {
+            if client.bytes_acked > 10_000 && server.bytes_acked == 2 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            (client, server) = counters();
+        }
+        assert_eq!(client.bytes_received, 2);
+        assert_eq!(server.bytes_received, 10_000);
+        // the SYN of the client is acknowledged too
+        assert_eq!(client.bytes_acked, 10_001);
+        assert_eq!(server.bytes_acked, 2);
+        assert_eq!(client.uid, unsafe { libc::getuid() });
+    }
+}
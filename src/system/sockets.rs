@@ -0,0 +1,117 @@
+use std::{collections::HashSet, fmt::Display, net::SocketAddr};
+
+use procfs::process::{FDTarget, Process};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+}
+
+impl Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Tcp6 => "tcp6",
+            Protocol::Udp => "udp",
+            Protocol::Udp6 => "udp6",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone)]
+pub struct Socket {
+    pub protocol: Protocol,
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub state: String,
+    pub inode: u64,
+    /// Bytes waiting in the receive and send queues
+    pub rx_queue: u32,
+    pub tx_queue: u32,
+}
+
+impl Socket {
+    /// Established connections, and udp sockets connected to a peer or with data queued
+    pub fn carries_traffic(&self) -> bool {
+        match self.protocol {
+            Protocol::Tcp | Protocol::Tcp6 => self.state == "Established",
+            Protocol::Udp | Protocol::Udp6 => {
+                self.remote.port() != 0 || self.rx_queue > 0 || self.tx_queue > 0
+            }
+        }
+    }
+}
+
+/// Inodes of the sockets opened by the process, from `/proc/<pid>/fd`
+pub fn socket_inodes(process: &Process) -> HashSet<u64> {
+    let Ok(fds) = process.fd() else {
+        return HashSet::new();
+    };
+
+    fds.filter_map(|fd| match fd.ok()?.target {
+        FDTarget::Socket(inode) => Some(inode),
+        _ => None,
+    })
+    .collect()
+}
+
+/// Every tcp/udp socket of the network namespace of the process, from `/proc/<pid>/net/{tcp,udp}{,6}`
+pub fn sockets_in_namespace_of(process: &Process) -> Vec<Socket> {
+    let tcp = [
+        (Protocol::Tcp, process.tcp()),
+        (Protocol::Tcp6, process.tcp6()),
+    ]
+    .into_iter()
+    .flat_map(|(protocol, entries)| {
+        entries
+            .unwrap_or_default()
+            .into_iter()
+            .map(move |entry| Socket {
+                protocol,
+                local: entry.local_address,
+                remote: entry.remote_address,
+                state: format!("{:?}", entry.state),
+                inode: entry.inode,
+                rx_queue: entry.rx_queue,
+                tx_queue: entry.tx_queue,
+            })
+    });
+    let udp = [
+        (Protocol::Udp, process.udp()),
+        (Protocol::Udp6, process.udp6()),
+    ]
+    .into_iter()
+    .flat_map(|(protocol, entries)| {
+        entries
+            .unwrap_or_default()
+            .into_iter()
+            .map(move |entry| Socket {
+                protocol,
+                local: entry.local_address,
+                remote: entry.remote_address,
+                state: format!("{:?}", entry.state),
+                inode: entry.inode,
+                rx_queue: entry.rx_queue,
+                tx_queue: entry.tx_queue,
+            })
+    });
+
+    tcp.chain(udp).collect()
+}
+
+/// Sockets opened by the process
+pub fn sockets_of_process(process: &Process) -> Vec<Socket> {
+    let inodes = socket_inodes(process);
+    if inodes.is_empty() {
+        return vec![];
+    }
+
+    sockets_in_namespace_of(process)
+        .into_iter()
+        .filter(|socket| inodes.contains(&socket.inode))
+        .collect()
+}
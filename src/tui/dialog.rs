@@ -0,0 +1,95 @@
+use crate::system::process::SIGNALS;
+
+/// What can be done to a process from the process table
+#[derive(Clone, Copy)]
+pub enum ProcessAction {
+    /// Index in `SIGNALS`
+    Signal(usize),
+    Renice(i32),
+}
+
+impl ProcessAction {
+    pub fn describe(&self) -> String {
+        match self {
+            ProcessAction::Signal(index) => format!("send {} to", SIGNALS[*index].0),
+            ProcessAction::Renice(nice) => format!("renice to {nice}"),
+        }
+    }
+}
+
+pub enum DialogStep {
+    Next(Dialog),
+    /// The action was confirmed on the process with this pid and start time
+    Confirmed(i32, u64, ProcessAction),
+}
+
+/// Modal dialogs acting on a process, they always end with a confirmation
+///
+/// The start time of the process, in clock ticks since boot, tells whether its pid was reused meanwhile.
+pub enum Dialog {
+    SignalPicker {
+        pid: i32,
+        start_time: u64,
+        name: String,
+        cursor: usize,
+    },
+    Renice {
+        pid: i32,
+        start_time: u64,
+        name: String,
+        nice: i32,
+    },
+    Confirm {
+        pid: i32,
+        start_time: u64,
+        name: String,
+        action: ProcessAction,
+    },
+}
+
+impl Dialog {
+    pub fn move_cursor(&mut self, offset: isize) {
+        match self {
+            Dialog::SignalPicker { cursor, .. } => {
+                *cursor = cursor.saturating_add_signed(offset).min(SIGNALS.len() - 1);
+            }
+            // up raises the priority, i.e. lowers the niceness
+            Dialog::Renice { nice, .. } => *nice = (*nice - offset as i32).clamp(-20, 19),
+            Dialog::Confirm { .. } => {}
+        }
+    }
+
+    /// Next step of the dialog once the choice is validated
+    pub fn validate(self) -> DialogStep {
+        match self {
+            Dialog::SignalPicker {
+                pid,
+                start_time,
+                name,
+                cursor,
+            } => DialogStep::Next(Dialog::Confirm {
+                pid,
+                start_time,
+                name,
+                action: ProcessAction::Signal(cursor),
+            }),
+            Dialog::Renice {
+                pid,
+                start_time,
+                name,
+                nice,
+            } => DialogStep::Next(Dialog::Confirm {
+                pid,
+                start_time,
+                name,
+                action: ProcessAction::Renice(nice),
+            }),
+            Dialog::Confirm {
+                pid,
+                start_time,
+                action,
+                ..
+            } => DialogStep::Confirmed(pid, start_time, action),
+        }
+    }
+}
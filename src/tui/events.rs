@@ -1,30 +1,90 @@
-#[derive(PartialEq, Eq)]
+use crossterm::event::{
+    KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+
+use super::tabs::Tab;
+
+/// Actions of the TUI, produced from the key presses by the `Keymap`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Event {
     Tick,
     Quit,
+    ToggleHelp,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Select,
+    Back,
+    ToggleInterfacePicker,
+    ToggleIdleInterfaces,
+    SelectTab(Tab),
+    NextTab,
+    PreviousTab,
+    /// Switch between bits and bytes
+    ToggleBits,
+    CycleRateWindow,
+    /// Chart a longer time span from the long-term history
+    ZoomOut,
+    ZoomIn,
+    /// Switch the totals between since the start and since the last reset
+    ToggleTotals,
+    ResetTotals,
+    StartSearch,
+    TogglePause,
+    /// Move the viewed tick back in time
+    StepBack,
+    StepForward,
+    SignalProcess,
+    ReniceProcess,
+    ReplaySlower,
+    ReplayFaster,
+    /// Move back in a replayed recording
+    SeekBack,
+    SeekForward,
+    /// A character typed while editing a text
+    Input(char),
+    DeleteChar,
+    /// Left click at a (column, row) position of the terminal
+    Click(u16, u16),
+    ScrollUp,
+    ScrollDown,
 }
 
-fn is_shutdown_event(key_event: crossterm::event::KeyEvent) -> bool {
-    if key_event.kind != crossterm::event::KeyEventKind::Press {
-        false
-    } else if matches!(key_event.code, crossterm::event::KeyCode::Char('q')) {
-        true
-    } else {
-        matches!(key_event.code, crossterm::event::KeyCode::Char('c'))
-            && matches!(key_event.modifiers, crossterm::event::KeyModifiers::CONTROL)
-    }
-}
 #[derive(PartialEq, Eq)]
 pub struct NoOp;
-impl TryFrom<crossterm::event::Event> for Event {
-    type Error = NoOp;
 
-    fn try_from(value: crossterm::event::Event) -> Result<Self, Self::Error> {
-        if let crossterm::event::Event::Key(key) = value {
-            if is_shutdown_event(key) {
-                return Ok(Event::Quit);
-            }
+impl Event {
+    /// Mouse events are not configurable, unlike the keys
+    pub fn from_mouse(mouse: MouseEvent) -> Result<Self, NoOp> {
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => Ok(Event::Click(mouse.column, mouse.row)),
+            MouseEventKind::ScrollUp => Ok(Event::ScrollUp),
+            MouseEventKind::ScrollDown => Ok(Event::ScrollDown),
+            _ => Err(NoOp),
+        }
+    }
+
+    /// While editing a text, the keys are characters instead of shortcuts
+    pub fn from_text_input(value: crossterm::event::Event) -> Result<Self, NoOp> {
+        let crossterm::event::Event::Key(key) = value else {
+            return Err(NoOp);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Err(NoOp);
+        }
+        if key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL {
+            return Ok(Event::Quit);
+        }
+
+        match key.code {
+            KeyCode::Char(c) => Ok(Event::Input(c)),
+            KeyCode::Backspace => Ok(Event::DeleteChar),
+            KeyCode::Enter => Ok(Event::Select),
+            KeyCode::Esc => Ok(Event::Back),
+            _ => Err(NoOp),
         }
-        Err(NoOp)
     }
 }
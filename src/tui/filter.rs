@@ -0,0 +1,260 @@
+use crate::bandwidth_tracker::bytes::parse_rate;
+
+use super::process_table::ProcessRow;
+
+/// Filter of the process table, made of whitespace separated terms that must all match
+///
+/// A term is either a text searched in the name, pid, cmdline and user of the process,
+/// or a comparison like `rate>1MB`, `user=postgres` or `cmdline~nginx`.
+#[derive(Debug, Default)]
+pub struct Filter {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Term {
+    Text(String),
+    TextComparison(Field, TextOperator, String),
+    NumberComparison(Field, NumberOperator, f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Pid,
+    Name,
+    User,
+    Cmdline,
+    /// Sum of the send and received rates
+    Rate,
+    Send,
+    Received,
+    /// Sum of the bytes send and received, since the start or the last reset as the total column shows it
+    Total,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextOperator {
+    Equal,
+    NotEqual,
+    Contains,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberOperator {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Lower,
+    LowerOrEqual,
+}
+
+/// Longest operators first so that `>=` is not read as `>`
+const OPERATORS: [&str; 7] = [">=", "<=", "!=", ">", "<", "=", "~"];
+
+impl Field {
+    fn parse(name: &str) -> Result<Field, String> {
+        match name.to_lowercase().as_str() {
+            "pid" => Ok(Field::Pid),
+            "name" | "comm" => Ok(Field::Name),
+            "user" => Ok(Field::User),
+            "cmdline" | "cmd" => Ok(Field::Cmdline),
+            "rate" => Ok(Field::Rate),
+            "send" | "tx" => Ok(Field::Send),
+            "received" | "rx" => Ok(Field::Received),
+            "total" => Ok(Field::Total),
+            _ => Err(format!("unknown field `{name}`")),
+        }
+    }
+
+    fn is_text(&self) -> bool {
+        matches!(self, Field::Name | Field::User | Field::Cmdline)
+    }
+
+    fn text_of<'a>(&self, row: &'a ProcessRow) -> &'a str {
+        match self {
+            Field::User => &row.user,
+            Field::Cmdline => &row.cmdline,
+            _ => &row.name,
+        }
+    }
+
+    fn number_of(&self, row: &ProcessRow) -> f64 {
+        match self {
+            Field::Pid => row.pid as f64,
+            Field::Rate => f64::from(row.send) + f64::from(row.received),
+            Field::Send => f64::from(row.send),
+            Field::Received => f64::from(row.received),
+            _ => f64::from(row.total_send) + f64::from(row.total_received),
+        }
+    }
+
+    fn parse_value(&self, value: &str) -> Result<f64, String> {
+        if *self == Field::Pid {
+            return value
+                .parse::<i32>()
+                .map(f64::from)
+                .map_err(|_| format!("invalid pid `{value}`"));
+        }
+
+        parse_rate(value).map(f64::from)
+    }
+}
+
+impl Term {
+    fn parse(term: &str) -> Result<Term, String> {
+        let Some((index, operator)) = OPERATORS
+            .iter()
+            .filter_map(|operator| term.find(operator).map(|index| (index, *operator)))
+            .min_by_key(|(index, operator)| (*index, usize::MAX - operator.len()))
+        else {
+            return Ok(Term::Text(term.to_lowercase()));
+        };
+
+        let field = Field::parse(&term[..index])?;
+        let value = &term[index + operator.len()..];
+        if value.is_empty() {
+            return Err(format!("missing value after `{operator}`"));
+        }
+
+        if field.is_text() {
+            let operator = match operator {
+                "=" => TextOperator::Equal,
+                "!=" => TextOperator::NotEqual,
+                "~" => TextOperator::Contains,
+                _ => return Err(format!("`{operator}` cannot compare text")),
+            };
+            Ok(Term::TextComparison(field, operator, value.to_lowercase()))
+        } else {
+            let operator = match operator {
+                "=" => NumberOperator::Equal,
+                "!=" => NumberOperator::NotEqual,
+                ">" => NumberOperator::Greater,
+                ">=" => NumberOperator::GreaterOrEqual,
+                "<" => NumberOperator::Lower,
+                "<=" => NumberOperator::LowerOrEqual,
+                _ => return Err(format!("`{operator}` cannot compare numbers")),
+            };
+            Ok(Term::NumberComparison(
+                field,
+                operator,
+                field.parse_value(value)?,
+            ))
+        }
+    }
+
+    fn matches(&self, row: &ProcessRow) -> bool {
+        match self {
+            Term::Text(text) => {
+                row.pid.to_string().contains(text.as_str())
+                    || [&row.name, &row.cmdline, &row.user]
+                        .iter()
+                        .any(|field| field.to_lowercase().contains(text.as_str()))
+            }
+            Term::TextComparison(field, operator, text) => {
+                let value = field.text_of(row).to_lowercase();
+                match operator {
+                    TextOperator::Equal => value == *text,
+                    TextOperator::NotEqual => value != *text,
+                    TextOperator::Contains => value.contains(text.as_str()),
+                }
+            }
+            Term::NumberComparison(field, operator, number) => {
+                let value = field.number_of(row);
+                match operator {
+                    NumberOperator::Equal => value == *number,
+                    NumberOperator::NotEqual => value != *number,
+                    NumberOperator::Greater => value > *number,
+                    NumberOperator::GreaterOrEqual => value >= *number,
+                    NumberOperator::Lower => value < *number,
+                    NumberOperator::LowerOrEqual => value <= *number,
+                }
+            }
+        }
+    }
+}
+
+impl Filter {
+    pub fn parse(filter: &str) -> Result<Filter, String> {
+        let terms = filter
+            .split_whitespace()
+            .map(Term::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Filter { terms })
+    }
+
+    pub fn matches(&self, row: &ProcessRow) -> bool {
+        self.terms.iter().all(|term| term.matches(row))
+    }
+}
+
+#[cfg(test)]
+mod tests_filter {
+    use std::time::Duration;
+
+    use crate::{bandwidth_tracker::bytes::BytesPerSecond, tui::process_table::ProcessRow};
+
+    use super::Filter;
+
+    fn row(pid: i32, name: &str, user: &str, bytes_per_second: u64) -> ProcessRow {
+        ProcessRow {
+            pid,
+            name: name.to_string(),
+            cmdline: format!("/usr/bin/{name} --daemon"),
+            user: user.to_string(),
+            received: BytesPerSecond::new(bytes_per_second.into(), Duration::from_secs(1)),
+            send: Default::default(),
+            total_received: Default::default(),
+            total_send: Default::default(),
+            history: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_text_matches_any_field() {
+        let filter = Filter::parse("DAEMON").unwrap();
+
+        assert!(filter.matches(&row(1, "nginx", "www", 0)));
+        assert!(Filter::parse("42")
+            .unwrap()
+            .matches(&row(42, "nginx", "www", 0)));
+        assert!(!Filter::parse("curl")
+            .unwrap()
+            .matches(&row(1, "nginx", "www", 0)));
+    }
+
+    #[test]
+    fn test_user_equality() {
+        let filter = Filter::parse("user=postgres").unwrap();
+
+        assert!(filter.matches(&row(1, "postgres", "postgres", 0)));
+        assert!(!filter.matches(&row(1, "psql", "postgresql", 0)));
+    }
+
+    #[test]
+    fn test_rate_comparison_with_units() {
+        let filter = Filter::parse("rate>1MB").unwrap();
+
+        assert!(filter.matches(&row(1, "curl", "me", 2_000_000)));
+        assert!(!filter.matches(&row(1, "curl", "me", 1_000_000)));
+        assert!(Filter::parse("rx>=1MB/s")
+            .unwrap()
+            .matches(&row(1, "curl", "me", 1_000_000)));
+    }
+
+    #[test]
+    fn test_all_terms_must_match() {
+        let filter = Filter::parse("user=www cmdline~nginx").unwrap();
+
+        assert!(filter.matches(&row(1, "nginx", "www", 0)));
+        assert!(!filter.matches(&row(1, "nginx", "root", 0)));
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!(Filter::parse("color=red").is_err());
+        assert!(Filter::parse("rate>fast").is_err());
+        assert!(Filter::parse("user>root").is_err());
+        assert!(Filter::parse("pid=").is_err());
+    }
+}
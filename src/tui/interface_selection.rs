@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{config::InterfacesConfig, system::link::LinkInfo};
+
+use super::state::ThroughputDatasets;
+
+/// Which interfaces are plotted on the chart
+///
+/// The include/exclude rules give the initial visibility of an interface, the picker then toggles it.
+pub struct InterfaceSelection {
+    config: InterfacesConfig,
+    visible: BTreeMap<String, bool>,
+    idle: BTreeSet<String>,
+    pub hide_idle: bool,
+    /// Position of the cursor in the picker, `None` when the picker is closed
+    pub picker_cursor: Option<usize>,
+}
+
+impl InterfaceSelection {
+    pub fn new(config: InterfacesConfig) -> InterfaceSelection {
+        InterfaceSelection {
+            hide_idle: config.hide_idle,
+            config,
+            visible: BTreeMap::new(),
+            idle: BTreeSet::new(),
+            picker_cursor: None,
+        }
+    }
+
+    pub fn refresh(
+        &mut self,
+        datasets: &BTreeMap<String, ThroughputDatasets>,
+        links: &BTreeMap<String, LinkInfo>,
+    ) {
+        self.visible
+            .retain(|interface, _| datasets.contains_key(interface));
+        for interface in datasets.keys() {
+            if !self.visible.contains_key(interface) {
+                let visible = self.is_allowed_by_rules(interface);
+                self.visible.insert(interface.clone(), visible);
+            }
+        }
+
+        self.idle = datasets
+            .iter()
+            .filter(|(interface, points)| {
+                links.get(*interface).is_some_and(LinkInfo::is_down)
+                    || points
+                        .received
+                        .iter()
+                        .chain(points.send.iter())
+                        .all(|(_, y)| *y == 0.)
+            })
+            .map(|(interface, _)| interface.clone())
+            .collect();
+
+        if let Some(cursor) = self.picker_cursor {
+            self.picker_cursor = Some(cursor.min(self.visible.len().saturating_sub(1)));
+        }
+    }
+
+    fn is_allowed_by_rules(&self, interface: &str) -> bool {
+        let included = self.config.include.is_empty()
+            || self
+                .config
+                .include
+                .iter()
+                .any(|pattern| pattern.matches(interface));
+        let excluded = self
+            .config
+            .exclude
+            .iter()
+            .any(|pattern| pattern.matches(interface));
+        included && !excluded
+    }
+
+    pub fn is_charted(&self, interface: &str) -> bool {
+        let visible = self.visible.get(interface).copied().unwrap_or(false);
+        visible && !(self.hide_idle && self.idle.contains(interface))
+    }
+
+    /// Every known interface with its (visible, idle) flags
+    pub fn interfaces(&self) -> impl Iterator<Item = (&String, bool, bool)> + '_ {
+        self.visible
+            .iter()
+            .map(|(interface, visible)| (interface, *visible, self.idle.contains(interface)))
+    }
+
+    pub fn toggle_picker(&mut self) {
+        self.picker_cursor = match self.picker_cursor {
+            Some(_) => None,
+            None => Some(0),
+        };
+    }
+
+    pub fn move_cursor(&mut self, offset: isize) {
+        if let Some(cursor) = self.picker_cursor {
+            let last = self.visible.len().saturating_sub(1);
+            self.picker_cursor = Some(cursor.saturating_add_signed(offset).min(last));
+        }
+    }
+
+    pub fn toggle(&mut self, interface: &str) {
+        if let Some(visible) = self.visible.get_mut(interface) {
+            *visible = !*visible;
+        }
+    }
+
+    pub fn toggle_under_cursor(&mut self) {
+        if let Some(visible) = self
+            .picker_cursor
+            .and_then(|cursor| self.visible.values_mut().nth(cursor))
+        {
+            *visible = !*visible;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_interface_selection {
+    use std::collections::BTreeMap;
+
+    use crate::{config::InterfacesConfig, system::link::LinkInfo, tui::state::ThroughputDatasets};
+
+    use super::InterfaceSelection;
+
+    fn datasets(interfaces: &[(&str, f64)]) -> BTreeMap<String, ThroughputDatasets> {
+        interfaces
+            .iter()
+            .map(|(interface, rate)| {
+                let points = ThroughputDatasets {
+                    received: vec![(-1., 0.), (0., *rate)],
+                    send: vec![(-1., 0.), (0., 0.)],
+                };
+                (interface.to_string(), points)
+            })
+            .collect()
+    }
+
+    fn link(operstate: &str) -> LinkInfo {
+        LinkInfo {
+            operstate: operstate.to_string(),
+            mtu: None,
+            speed: None,
+            carrier_changes: None,
+        }
+    }
+
+    fn with_rules(include: &[&str], exclude: &[&str]) -> InterfaceSelection {
+        let patterns = |patterns: &[&str]| {
+            patterns
+                .iter()
+                .map(|pattern| pattern.parse().unwrap())
+                .collect()
+        };
+        InterfaceSelection::new(InterfacesConfig {
+            include: patterns(include),
+            exclude: patterns(exclude),
+            hide_idle: false,
+        })
+    }
+
+    #[test]
+    fn test_exclude_wins_over_include() {
+        let mut selection = with_rules(&["eth*", "veth*"], &["veth*"]);
+        let datasets = datasets(&[("eth0", 1.), ("veth1a2b", 1.), ("wlan0", 1.)]);
+        selection.refresh(&datasets, &BTreeMap::new());
+
+        assert!(selection.is_charted("eth0"));
+        assert!(!selection.is_charted("veth1a2b"));
+        assert!(!selection.is_charted("wlan0"));
+
+        let mut selection = with_rules(&[], &["veth*"]);
+        selection.refresh(&datasets, &BTreeMap::new());
+        assert!(selection.is_charted("wlan0"));
+        assert!(!selection.is_charted("veth1a2b"));
+    }
+
+    #[test]
+    fn test_down_and_idle_interfaces_are_hidden() {
+        let mut selection = with_rules(&[], &[]);
+        let datasets = datasets(&[("eth0", 1.), ("eth1", 1.), ("lo", 0.)]);
+        let links = [
+            ("eth0".to_string(), link("up")),
+            ("eth1".to_string(), link("down")),
+        ]
+        .into();
+        selection.refresh(&datasets, &links);
+        assert!(selection.is_charted("eth1"));
+        assert!(selection.is_charted("lo"));
+
+        selection.hide_idle = true;
+        assert!(selection.is_charted("eth0"));
+        assert!(!selection.is_charted("eth1"));
+        assert!(!selection.is_charted("lo"));
+        let idle: Vec<_> = selection
+            .interfaces()
+            .map(|(interface, _, idle)| (interface.as_str(), idle))
+            .collect();
+        assert_eq!(idle, [("eth0", false), ("eth1", true), ("lo", true)]);
+    }
+
+    #[test]
+    fn test_picker_toggles_the_interface_under_the_cursor() {
+        let mut selection = with_rules(&[], &["docker*"]);
+        let datasets = datasets(&[("docker0", 1.), ("eth0", 1.), ("wlan0", 1.)]);
+        selection.refresh(&datasets, &BTreeMap::new());
+
+        selection.toggle_picker();
+        selection.toggle_under_cursor();
+        assert!(selection.is_charted("docker0"));
+        selection.move_cursor(5);
+        assert_eq!(selection.picker_cursor, Some(2));
+        selection.toggle_under_cursor();
+        assert!(!selection.is_charted("wlan0"));
+
+        // the choice of the picker stays over the refreshes
+        selection.refresh(&datasets, &BTreeMap::new());
+        assert!(selection.is_charted("docker0"));
+        assert!(!selection.is_charted("wlan0"));
+
+        selection.toggle("wlan0");
+        assert!(selection.is_charted("wlan0"));
+        selection.toggle_picker();
+        assert_eq!(selection.picker_cursor, None);
+    }
+}
@@ -0,0 +1,481 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+use crate::config::KeyList;
+
+use super::{
+    events::{Event, NoOp},
+    tabs::Tab,
+};
+
+/// Actions that can be bound to keys: (action, name in the config, description, default keys)
+const BINDABLE_ACTIONS: [(Event, &str, &str, &[&str]); 36] = [
+    (Event::Quit, "quit", "quit", &["q", "ctrl+c"]),
+    (Event::ToggleHelp, "help", "show or hide this help", &["?"]),
+    (Event::Up, "up", "move up", &["up", "k"]),
+    (Event::Down, "down", "move down", &["down", "j"]),
+    (Event::PageUp, "page_up", "move one page up", &["pageup"]),
+    (
+        Event::PageDown,
+        "page_down",
+        "move one page down",
+        &["pagedown"],
+    ),
+    (Event::Home, "first", "go to the first row", &["home", "g"]),
+    (Event::End, "last", "go to the last row", &["end", "G"]),
+    (
+        Event::Select,
+        "select",
+        "open the process details, toggle, confirm",
+        &["enter", "space"],
+    ),
+    (Event::Back, "back", "close, cancel, unselect", &["esc"]),
+    (Event::StartSearch, "filter", "filter the processes", &["/"]),
+    (
+        Event::TogglePause,
+        "pause",
+        "pause or resume the display",
+        &["p"],
+    ),
+    (Event::StepBack, "step_back", "move back in time", &["left"]),
+    (
+        Event::StepForward,
+        "step_forward",
+        "move forward in time",
+        &["right"],
+    ),
+    (
+        Event::SignalProcess,
+        "signal",
+        "send a signal to the process",
+        &["K"],
+    ),
+    (
+        Event::ReniceProcess,
+        "renice",
+        "change the niceness of the process",
+        &["r"],
+    ),
+    (
+        Event::ReplaySlower,
+        "replay_slower",
+        "replay the recording slower",
+        &["-"],
+    ),
+    (
+        Event::ReplayFaster,
+        "replay_faster",
+        "replay the recording faster",
+        &["+"],
+    ),
+    (
+        Event::SeekBack,
+        "seek_back",
+        "go 10s back in the recording",
+        &["["],
+    ),
+    (
+        Event::SeekForward,
+        "seek_forward",
+        "go 10s forward in the recording",
+        &["]"],
+    ),
+    (
+        Event::ToggleInterfacePicker,
+        "interfaces",
+        "pick the interfaces of the chart",
+        &["i"],
+    ),
+    (
+        Event::ToggleIdleInterfaces,
+        "hide_idle",
+        "hide the idle interfaces",
+        &["z"],
+    ),
+    (Event::NextTab, "next_tab", "show the next tab", &["tab"]),
+    (
+        Event::PreviousTab,
+        "previous_tab",
+        "show the previous tab",
+        &["backtab"],
+    ),
+    (
+        Event::SelectTab(Tab::Processes),
+        "processes_tab",
+        "show the processes",
+        &["1"],
+    ),
+    (
+        Event::SelectTab(Tab::Interfaces),
+        "interfaces_tab",
+        "show the interfaces statistics",
+        &["2"],
+    ),
+    (
+        Event::SelectTab(Tab::Connections),
+        "connections_tab",
+        "show the connections",
+        &["3"],
+    ),
+    (
+        Event::SelectTab(Tab::Hosts),
+        "hosts_tab",
+        "show the remote hosts",
+        &["4"],
+    ),
+    (
+        Event::SelectTab(Tab::Log),
+        "log_tab",
+        "show the log",
+        &["5"],
+    ),
+    (
+        Event::SelectTab(Tab::Usage),
+        "usage_tab",
+        "show the data usage and the quotas",
+        &["6"],
+    ),
+    (
+        Event::ToggleBits,
+        "bits",
+        "switch between bits and bytes",
+        &["b"],
+    ),
+    (
+        Event::CycleRateWindow,
+        "rate_window",
+        "change the window of the rates: instant, 1s, 5s, 30s, 5m",
+        &["w"],
+    ),
+    (
+        Event::ZoomOut,
+        "zoom_out",
+        "chart a longer time: 5 minutes, 1 hour, 1 day, 30 days",
+        &["<"],
+    ),
+    (
+        Event::ZoomIn,
+        "zoom_in",
+        "chart a shorter time, down to the live chart",
+        &[">"],
+    ),
+    (
+        Event::ToggleTotals,
+        "totals",
+        "show the totals since the start or since the reset",
+        &["t"],
+    ),
+    (
+        Event::ResetTotals,
+        "reset_totals",
+        "zero the totals since reset",
+        &["0"],
+    ),
+];
+
+/// A key with its modifiers, like `ctrl+c`
+///
+/// The shift modifier of characters is carried by the character itself: `shift+k` is `K`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => KeyChord {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            // terminals report shift+tab as a backtab with the shift modifier
+            KeyCode::BackTab => KeyChord {
+                code,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            _ => KeyChord { code, modifiers },
+        }
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        KeyChord::new(key.code, key.modifiers)
+    }
+}
+
+fn parse_key_code(name: &str) -> Result<KeyCode, String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c));
+    }
+
+    let code = match name.to_lowercase().as_str() {
+        "enter" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        function => match function.strip_prefix('f').map(str::parse::<u8>) {
+            Some(Ok(number @ 1..=12)) => KeyCode::F(number),
+            _ => return Err(format!("unknown key `{name}`")),
+        },
+    };
+    Ok(code)
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(chord: &str) -> Result<Self, Self::Err> {
+        // `+` alone, or at the end of `ctrl++`, is the key itself
+        let (modifiers, key) = match chord.strip_suffix("++") {
+            _ if chord == "+" => ("", "+"),
+            Some(modifiers) => (modifiers, "+"),
+            None => chord.rsplit_once('+').unwrap_or(("", chord)),
+        };
+        if key.is_empty() {
+            return Err(format!("missing key in `{chord}`"));
+        }
+
+        let modifiers = modifiers
+            .split('+')
+            .filter(|modifier| !modifier.is_empty())
+            .map(|modifier| match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => Ok(KeyModifiers::CONTROL),
+                "alt" => Ok(KeyModifiers::ALT),
+                "shift" => Ok(KeyModifiers::SHIFT),
+                _ => Err(format!("unknown modifier `{modifier}` in `{chord}`")),
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .fold(KeyModifiers::NONE, |all, modifier| all | modifier);
+
+        Ok(KeyChord::new(parse_key_code(key)?, modifiers))
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(number) => write!(f, "f{number}"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            code => write!(f, "{}", code.to_string().to_lowercase().replace(' ', "")),
+        }
+    }
+}
+
+/// Keys bound to each action, the defaults are replaced action by action from the config
+pub struct Keymap {
+    bindings: Vec<(Event, &'static str, Vec<KeyChord>)>,
+    actions: HashMap<KeyChord, Event>,
+}
+
+impl Keymap {
+    /// Fails on unknown actions or keys, and when a key is bound to several actions
+    pub fn new(overrides: &HashMap<String, KeyList>) -> Result<Keymap, String> {
+        if let Some(unknown) = overrides
+            .keys()
+            .find(|name| !BINDABLE_ACTIONS.iter().any(|action| action.1 == *name))
+        {
+            return Err(format!("unknown action `{unknown}` in the keybindings"));
+        }
+
+        let mut bindings = Vec::new();
+        for (action, name, description, default_keys) in BINDABLE_ACTIONS {
+            let keys: Vec<&str> = match overrides.get(name) {
+                Some(keys) => keys.iter().collect(),
+                None => default_keys.to_vec(),
+            };
+            let chords = keys
+                .into_iter()
+                .map(str::parse)
+                .collect::<Result<Vec<KeyChord>, _>>()
+                .map_err(|error| format!("keybinding of `{name}`: {error}"))?;
+            bindings.push((action, description, chords));
+        }
+
+        let mut actions = HashMap::new();
+        let mut conflicts = Vec::new();
+        for (action, _, chords) in &bindings {
+            for chord in chords {
+                match actions.insert(*chord, *action) {
+                    Some(other) if other != *action => conflicts.push(format!(
+                        "`{chord}` is bound to both `{}` and `{}`",
+                        action_name(other),
+                        action_name(*action)
+                    )),
+                    _ => {}
+                }
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(format!("conflicting keybindings: {}", conflicts.join(", ")));
+        }
+
+        Ok(Keymap { bindings, actions })
+    }
+
+    pub fn action_for(&self, event: crossterm::event::Event) -> Result<Event, NoOp> {
+        match event {
+            crossterm::event::Event::Key(key) if key.kind == KeyEventKind::Press => {
+                self.actions.get(&key.into()).copied().ok_or(NoOp)
+            }
+            crossterm::event::Event::Mouse(mouse) => Event::from_mouse(mouse),
+            _ => Err(NoOp),
+        }
+    }
+
+    /// Keys of the action joined by `/`, as shown in the help
+    pub fn keys_of(&self, action: Event) -> String {
+        self.bindings
+            .iter()
+            .find(|(bound, _, _)| *bound == action)
+            .map(|(_, _, chords)| {
+                chords
+                    .iter()
+                    .map(KeyChord::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .unwrap_or_default()
+    }
+
+    /// (keys, description) of every action
+    pub fn help(&self) -> impl Iterator<Item = (String, &'static str)> + '_ {
+        self.bindings
+            .iter()
+            .map(|(action, description, _)| (self.keys_of(*action), *description))
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&HashMap::new()).expect("the default keybindings are valid")
+    }
+}
+
+fn action_name(action: Event) -> &'static str {
+    BINDABLE_ACTIONS
+        .iter()
+        .find(|(bound, ..)| *bound == action)
+        .map(|(_, name, ..)| *name)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests_keymap {
+    use std::collections::HashMap;
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::{config::KeyList, tui::events::Event};
+
+    use super::{KeyChord, Keymap};
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> crossterm::event::Event {
+        crossterm::event::Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn test_parse_chords() {
+        let chord = |code, modifiers| KeyChord::new(code, modifiers);
+
+        assert_eq!(
+            "ctrl+c".parse(),
+            Ok(chord(KeyCode::Char('c'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            "shift+k".parse(),
+            Ok(chord(KeyCode::Char('K'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            "PageUp".parse(),
+            Ok(chord(KeyCode::PageUp, KeyModifiers::NONE))
+        );
+        assert_eq!(
+            "+".parse(),
+            Ok(chord(KeyCode::Char('+'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            "alt++".parse(),
+            Ok(chord(KeyCode::Char('+'), KeyModifiers::ALT))
+        );
+        assert_eq!("f5".parse(), Ok(chord(KeyCode::F(5), KeyModifiers::NONE)));
+        assert!("hyper+x".parse::<KeyChord>().is_err());
+        assert!("f13".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn test_default_keymap() {
+        let keymap = Keymap::default();
+
+        assert!(
+            keymap.action_for(press(KeyCode::Char('q'), KeyModifiers::NONE)) == Ok(Event::Quit)
+        );
+        assert!(
+            keymap.action_for(press(KeyCode::Char('K'), KeyModifiers::SHIFT))
+                == Ok(Event::SignalProcess)
+        );
+        assert!(keymap
+            .action_for(press(KeyCode::Char('x'), KeyModifiers::NONE))
+            .is_err());
+    }
+
+    #[test]
+    fn test_override_replaces_the_default_keys() {
+        let overrides = HashMap::from([("pause".to_string(), KeyList::One("f2".to_string()))]);
+        let keymap = Keymap::new(&overrides).unwrap();
+
+        assert!(
+            keymap.action_for(press(KeyCode::F(2), KeyModifiers::NONE)) == Ok(Event::TogglePause)
+        );
+        assert!(keymap
+            .action_for(press(KeyCode::Char('p'), KeyModifiers::NONE))
+            .is_err());
+    }
+
+    #[test]
+    fn test_conflicts_are_detected() {
+        let overrides =
+            HashMap::from([("pause".to_string(), KeyList::Many(vec!["q".to_string()]))]);
+
+        let error = Keymap::new(&overrides).err().unwrap();
+
+        assert!(error.contains("`q` is bound to both `quit` and `pause`"));
+    }
+
+    #[test]
+    fn test_unknown_action() {
+        let overrides = HashMap::from([("fly".to_string(), KeyList::One("f".to_string()))]);
+
+        assert!(Keymap::new(&overrides).is_err());
+    }
+}
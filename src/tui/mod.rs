@@ -1,3 +1,10 @@
+pub mod dialog;
 pub mod events;
+pub mod filter;
+pub mod interface_selection;
+pub mod keymap;
+pub mod process_table;
 pub mod render;
 pub mod state;
+pub mod tabs;
+pub mod theme;
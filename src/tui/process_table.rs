@@ -0,0 +1,260 @@
+use std::cmp::Ordering;
+
+use ratatui::widgets::TableState;
+
+use crate::bandwidth_tracker::bytes::{BytesPerSecond, NumberOfBytes};
+
+/// A line of the process table
+pub struct ProcessRow {
+    pub pid: i32,
+    pub name: String,
+    pub cmdline: String,
+    pub user: String,
+    pub received: BytesPerSecond,
+    pub send: BytesPerSecond,
+    pub total_received: NumberOfBytes,
+    pub total_send: NumberOfBytes,
+    /// (received, send) throughput of the last ticks, oldest first
+    pub history: Vec<(BytesPerSecond, BytesPerSecond)>,
+}
+
+/// Column the process table is sorted by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortColumn {
+    #[default]
+    Pid,
+    Name,
+    Send,
+    Received,
+    TotalSend,
+    TotalReceived,
+}
+
+impl SortColumn {
+    /// Columns in the order of the table
+    pub const ALL: [SortColumn; 6] = [
+        SortColumn::Pid,
+        SortColumn::Name,
+        SortColumn::Send,
+        SortColumn::Received,
+        SortColumn::TotalSend,
+        SortColumn::TotalReceived,
+    ];
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            SortColumn::Pid => "pid",
+            SortColumn::Name => "name",
+            SortColumn::Send => "send/s",
+            SortColumn::Received => "received/s",
+            SortColumn::TotalSend => "total send",
+            SortColumn::TotalReceived => "total received",
+        }
+    }
+
+    /// Texts are sorted A to Z first, numbers biggest first
+    fn is_descending_by_default(&self) -> bool {
+        !matches!(self, SortColumn::Pid | SortColumn::Name)
+    }
+
+    fn compare(&self, a: &ProcessRow, b: &ProcessRow) -> Ordering {
+        let ordering = match self {
+            SortColumn::Pid => a.pid.cmp(&b.pid),
+            SortColumn::Name => a.name.cmp(&b.name),
+            SortColumn::Send => a.send.partial_cmp(&b.send).unwrap_or(Ordering::Equal),
+            SortColumn::Received => a
+                .received
+                .partial_cmp(&b.received)
+                .unwrap_or(Ordering::Equal),
+            SortColumn::TotalSend => a.total_send.cmp(&b.total_send),
+            SortColumn::TotalReceived => a.total_received.cmp(&b.total_received),
+        };
+        ordering.then(a.pid.cmp(&b.pid))
+    }
+}
+
+/// Rows of the process table and its cursor
+///
+/// The cursor follows the pid of the selected process when the rows are refreshed,
+/// when the process disappears it stays at the same position.
+#[derive(Default)]
+pub struct ProcessTable {
+    pub rows: Vec<ProcessRow>,
+    pub state: TableState,
+    selected_pid: Option<i32>,
+    /// Number of rows visible at once, updated when the table is drawn
+    pub page_size: usize,
+    pub sort_column: SortColumn,
+    pub sort_descending: bool,
+}
+
+impl ProcessTable {
+    pub fn set_rows(&mut self, mut rows: Vec<ProcessRow>) {
+        rows.sort_by(|a, b| {
+            let ordering = self.sort_column.compare(a, b);
+            if self.sort_descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        self.rows = rows;
+
+        let index = self
+            .selected_pid
+            .and_then(|pid| self.rows.iter().position(|row| row.pid == pid))
+            .or(self.state.selected());
+        match index {
+            Some(index) => self.select(index),
+            None => self.unselect(),
+        }
+    }
+
+    /// Sort by the column, sorting again by the same column reverses the order
+    pub fn sort_by(&mut self, column: SortColumn) {
+        if self.sort_column == column {
+            self.sort_descending = !self.sort_descending;
+        } else {
+            self.sort_column = column;
+            self.sort_descending = column.is_descending_by_default();
+        }
+        let rows = std::mem::take(&mut self.rows);
+        self.set_rows(rows);
+    }
+
+    /// Select the row at this position from the top of the visible rows
+    pub fn select_visible(&mut self, position: usize) {
+        let index = self.state.offset() + position;
+        if index < self.rows.len() {
+            self.select(index);
+        }
+    }
+
+    pub fn selected_pid(&self) -> Option<i32> {
+        self.selected_pid
+    }
+
+    pub fn selected_index(&self) -> Option<usize> {
+        self.state.selected()
+    }
+
+    fn select(&mut self, index: usize) {
+        if self.rows.is_empty() {
+            self.state.select(None);
+            self.selected_pid = None;
+        } else {
+            let index = index.min(self.rows.len() - 1);
+            self.state.select(Some(index));
+            self.selected_pid = Some(self.rows[index].pid);
+        }
+    }
+
+    pub fn unselect(&mut self) {
+        self.state.select(None);
+        self.selected_pid = None;
+    }
+
+    pub fn move_selection(&mut self, offset: isize) {
+        match self.state.selected() {
+            Some(index) => self.select(index.saturating_add_signed(offset)),
+            None => self.select(0),
+        }
+    }
+
+    pub fn page_up(&mut self) {
+        self.move_selection(-(self.page_size.max(1) as isize));
+    }
+
+    pub fn page_down(&mut self) {
+        self.move_selection(self.page_size.max(1) as isize);
+    }
+
+    pub fn select_first(&mut self) {
+        self.select(0);
+    }
+
+    pub fn select_last(&mut self) {
+        self.select(self.rows.len().saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests_process_table {
+    use super::{ProcessRow, ProcessTable, SortColumn};
+
+    fn rows(pids: &[i32]) -> Vec<ProcessRow> {
+        pids.iter()
+            .map(|pid| ProcessRow {
+                pid: *pid,
+                name: String::new(),
+                cmdline: String::new(),
+                user: String::new(),
+                received: Default::default(),
+                send: Default::default(),
+                total_received: Default::default(),
+                total_send: Default::default(),
+                history: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cursor_follows_the_selected_pid() {
+        let mut table = ProcessTable::default();
+        table.set_rows(rows(&[1, 2, 3]));
+        table.select_last();
+
+        table.set_rows(rows(&[0, 1, 2, 3]));
+
+        assert_eq!(table.selected_pid(), Some(3));
+        assert_eq!(table.selected_index(), Some(3));
+    }
+
+    #[test]
+    fn test_cursor_stays_in_place_when_the_pid_disappears() {
+        let mut table = ProcessTable::default();
+        table.set_rows(rows(&[1, 2, 3]));
+        table.select_last();
+
+        table.set_rows(rows(&[1, 2]));
+
+        assert_eq!(table.selected_pid(), Some(2));
+        assert_eq!(table.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn test_paging_is_clamped_to_the_rows() {
+        let mut table = ProcessTable {
+            page_size: 10,
+            ..Default::default()
+        };
+        table.set_rows(rows(&[1, 2, 3]));
+
+        table.page_down();
+        assert_eq!(table.selected_pid(), Some(1));
+        table.page_down();
+        assert_eq!(table.selected_pid(), Some(3));
+        table.page_up();
+        assert_eq!(table.selected_pid(), Some(1));
+    }
+
+    #[test]
+    fn test_sort_by_column() {
+        let mut table = ProcessTable::default();
+        let mut unsorted = rows(&[1, 2, 3]);
+        unsorted[0].total_send = 20.into();
+        unsorted[1].total_send = 30.into();
+        unsorted[2].total_send = 10.into();
+        table.set_rows(unsorted);
+        table.select_first();
+
+        table.sort_by(SortColumn::TotalSend);
+        let pids: Vec<_> = table.rows.iter().map(|row| row.pid).collect();
+        assert_eq!(pids, [2, 1, 3]);
+        assert_eq!(table.selected_pid(), Some(1));
+
+        table.sort_by(SortColumn::TotalSend);
+        let pids: Vec<_> = table.rows.iter().map(|row| row.pid).collect();
+        assert_eq!(pids, [3, 1, 2]);
+    }
+}
@@ -1,103 +1,766 @@
-use std::time::Duration;
-
 use ratatui::{
-    layout::Layout,
+    layout::{Flex, Layout, Margin, Rect},
     prelude::Constraint,
-    style::{Color, Stylize},
-    text::Line,
-    widgets::{Axis, Block, Chart, Dataset, GraphType, Row, Table},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{
+        Axis, Block, Chart, Clear, Dataset, GraphType, List, ListItem, ListState, Paragraph, Row,
+        Scrollbar, ScrollbarOrientation, ScrollbarState, Table, Wrap,
+    },
     Frame,
 };
 
-use super::state::Model;
+use crate::{
+    bandwidth_tracker::{
+        bytes::{BytesPerSecond, UnitFormat},
+        store::ChartZoom,
+    },
+    config::ChartPlacement,
+    system::process::SIGNALS,
+};
+
+use super::{
+    dialog::Dialog,
+    events::Event,
+    keymap::Keymap,
+    process_table::SortColumn,
+    state::{Model, ProcessDetailPane, ScreenLayout},
+    tabs::Tab,
+    theme::Theme,
+};
+
+/// Terminals at least this wide get the chart beside the process table
+const WIDE_TERMINAL: u16 = 200;
+
+pub fn draw_state(frame: &mut Frame, state: &mut Model) {
+    state.layout = ScreenLayout::default();
+    let [tab_bar, main, status] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Fill(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    draw_tab_bar(frame, state, tab_bar);
+    frame.render_widget(get_status_line(state), status);
+
+    match state.tab {
+        Tab::Processes => match &state.process_detail {
+            Some(pane) => draw_process_detail(frame, state, pane, main),
+            None => draw_processes_tab(frame, state, main),
+        },
+        Tab::Interfaces => draw_interfaces_tab(frame, state, main),
+        Tab::Connections => state.connections.draw(frame, main, &state.units),
+        Tab::Hosts => state.hosts.draw(frame, main),
+        Tab::Log => state.log.draw(frame, main),
+        Tab::Usage => state.usage.draw(frame, main, &state.units, &state.theme),
+    }
+
+    if let Some(dialog) = &state.dialog {
+        draw_dialog(frame, dialog, main);
+    }
+    if state.show_help {
+        draw_help(frame, &state.keymap, main);
+    }
+}
+
+/// Titles of the tabs prefixed by their number, a click on a title selects the tab
+fn draw_tab_bar(frame: &mut Frame, state: &mut Model, area: Rect) {
+    let mut spans = Vec::new();
+    let mut x = area.x;
+    for (index, tab) in Tab::ALL.into_iter().enumerate() {
+        let title = format!(" {} {tab} ", index + 1);
+        let width = title.chars().count() as u16;
+        if tab == state.tab {
+            spans.push(title.bold().reversed());
+        } else {
+            spans.push(title.into());
+        }
+        state
+            .layout
+            .tabs
+            .push((Rect::new(x, area.y, width, 1), tab));
+        x += width;
+    }
+    frame.render_widget(Line::from(spans), area);
+}
+
+fn draw_processes_tab(frame: &mut Frame, state: &mut Model, area: Rect) {
+    let beside = match state.chart_placement {
+        ChartPlacement::Auto => area.width >= WIDE_TERMINAL,
+        ChartPlacement::Below => false,
+        ChartPlacement::Beside => true,
+    };
+    let [table_area, chart_area] = if beside {
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(area)
+    } else {
+        Layout::vertical([Constraint::Fill(1); 2]).areas(area)
+    };
+
+    draw_process_table(frame, state, table_area);
+    draw_chart(frame, state, chart_area);
+}
+
+fn draw_interfaces_tab(frame: &mut Frame, state: &mut Model, area: Rect) {
+    let [top, bottom] = Layout::vertical([Constraint::Fill(1); 2]).areas(area);
+
+    frame.render_widget(get_table_of_interface_statistics(state), top);
+    draw_chart(frame, state, bottom);
+}
+
+/// Chart of the interfaces with its legend, and the interface picker over it when open
+fn draw_chart(frame: &mut Frame, state: &mut Model, area: Rect) {
+    frame.render_widget(get_chart_of_global_thoughputs(state), area);
+    draw_chart_legend(frame, state, area);
+
+    if let Some(cursor) = state.interface_selection.picker_cursor {
+        draw_interface_picker(frame, state, cursor, area);
+    }
+}
 
-pub fn draw_state(frame: &mut Frame, state: &Model) {
+fn get_status_line<'a>(state: &Model) -> Line<'a> {
+    let mut status = if state.is_editing_filter() {
+        Line::from(vec![
+            "/".bold(),
+            state.filter_text.clone().into(),
+            "_".slow_blink(),
+        ])
+    } else if !state.filter_text.is_empty() {
+        Line::from(vec!["filter: ".bold(), state.filter_text.clone().into()])
+    } else {
+        let keymap = &state.keymap;
+        Line::from(format!(
+            "{}: quit  {}: help  {}: filter  {}: details",
+            keymap.keys_of(Event::Quit),
+            keymap.keys_of(Event::ToggleHelp),
+            keymap.keys_of(Event::StartSearch),
+            keymap.keys_of(Event::Select),
+        ))
+        .dim()
+    };
+
+    if let Some(paused_at) = state.paused_at {
+        let seconds_ago = state
+            .bandwidth_tracker
+            .last_tick()
+            .duration_since(paused_at)
+            .unwrap_or_default()
+            .as_secs_f64();
+        status.push_span(Span::styled(
+            format!(
+                "  paused, {seconds_ago:.1}s ago ({}/{}: move in time, {}: resume)",
+                state.keymap.keys_of(Event::StepBack),
+                state.keymap.keys_of(Event::StepForward),
+                state.keymap.keys_of(Event::TogglePause),
+            ),
+            state.theme.paused,
+        ));
+    }
+
+    if let Some(source_status) = state.source_status() {
+        let style = match state.is_approximate() {
+            true => state.theme.problem,
+            false => state.theme.paused,
+        };
+        status.push_span(Span::styled(format!("  {source_status}"), style));
+    }
+    if let Err(error) = &state.filter {
+        status.push_span(Span::styled(format!("  {error}"), state.theme.error));
+    }
+    if let Some(message) = &state.status_message {
+        status.push_span(Span::styled(format!("  {message}"), state.theme.message));
+    }
+    status
+}
+
+/// The sortable columns first, then the sparklines of the send and received throughputs
+const PROCESS_TABLE_WIDTHS: [Constraint; 8] = [
+    Constraint::Fill(1),
+    Constraint::Fill(1),
+    Constraint::Fill(1),
+    Constraint::Fill(1),
+    Constraint::Fill(1),
+    Constraint::Fill(1),
+    Constraint::Length(SPARKLINE_WIDTH as u16),
+    Constraint::Length(SPARKLINE_WIDTH as u16),
+];
+
+fn draw_process_table(frame: &mut Frame, state: &mut Model, area: Rect) {
     let table = get_table_data_per_process(state);
+    // header and footer take one line each
+    state.process_table.page_size = area.height.saturating_sub(2) as usize;
 
-    let [top, bottom] = Layout::vertical([Constraint::Fill(1); 2]).areas(frame.area());
+    // same split as the table: no highlight symbol, one cell of spacing between the columns
+    let header = Rect { height: 1, ..area };
+    let columns = Layout::horizontal(PROCESS_TABLE_WIDTHS)
+        .flex(Flex::Start)
+        .spacing(1)
+        .split(header);
+    state.layout.table_header = columns.iter().copied().zip(SortColumn::ALL).collect();
+    state.layout.table_rows = Rect {
+        y: area.y + 1,
+        height: area.height.saturating_sub(2),
+        ..area
+    };
+    frame.render_stateful_widget(table, area, &mut state.process_table.state);
 
-    let chart = get_chart_of_global_thoughputs(state);
-    frame.render_widget(table, top);
-    frame.render_widget(chart, bottom);
+    let mut scrollbar_state = ScrollbarState::new(state.process_table.rows.len())
+        .position(state.process_table.selected_index().unwrap_or_default());
+    frame.render_stateful_widget(
+        Scrollbar::new(ScrollbarOrientation::VerticalRight),
+        area.inner(Margin {
+            vertical: 1,
+            horizontal: 0,
+        }),
+        &mut scrollbar_state,
+    );
 }
 
-fn get_table_data_per_process<'a>(state: &'a Model<'a>) -> Table<'a> {
+fn get_table_data_per_process<'a>(state: &Model) -> Table<'a> {
     let tracker = &state.bandwidth_tracker;
-    let rows: Vec<_> = tracker
-        .get_throughput_over_duration(Duration::from_secs(5))
-        .filter_map(|(pid, received, send)| {
-            let process_name = state.process_by_pid.get(&pid)?.stat().ok()?.comm;
-            let total_bytes_received = tracker
-                .get_nbr_of_bytes_received_since_monitoring_started(pid)
-                .unwrap_or_default();
-            let total_bytes_send = tracker
-                .get_nbr_of_bytes_send_since_monitoring_started(pid)
+    let units = &state.units;
+    let rows: Vec<_> = state
+        .process_table
+        .rows
+        .iter()
+        .map(|row| {
+            Row::new([
+                row.pid.to_string(),
+                row.name.clone(),
+                units.rate(row.send),
+                units.rate(row.received),
+                units.bytes(row.total_send),
+                units.bytes(row.total_received),
+                sparkline(
+                    row.history.iter().map(|(_, send)| f64::from(*send)),
+                    SPARKLINE_WIDTH,
+                ),
+                sparkline(
+                    row.history.iter().map(|(received, _)| f64::from(*received)),
+                    SPARKLINE_WIDTH,
+                ),
+            ])
+            .style(if state.alerts.is_firing_for_pid(row.pid) {
+                state.theme.alert
+            } else {
+                state
+                    .theme
+                    .heat_of(row.send + row.received, &state.heat_thresholds)
+            })
+        })
+        .collect();
+
+    let (unattributed_received, unattributed_send) =
+        tracker.get_unattributed_throughput_at(state.view_time(), state.rate_window);
+    let unattributed = Row::new([
+        String::new(),
+        "unattributed".to_string(),
+        units.rate(unattributed_send),
+        units.rate(unattributed_received),
+        String::new(),
+        String::new(),
+        String::new(),
+        String::new(),
+    ])
+    .italic();
+
+    let process_table = &state.process_table;
+    let approximate = if state.is_approximate() { "≈ " } else { "" };
+    let sortable_header = SortColumn::ALL.map(|column| {
+        let title = match column {
+            // estimated traffic is marked in every header showing it
+            SortColumn::Send | SortColumn::Received => {
+                format!("{approximate}{} ({})", column.header(), state.rate_window)
+            }
+            SortColumn::TotalSend | SortColumn::TotalReceived => {
+                format!("{approximate}{} ({})", column.header(), state.totals_since)
+            }
+            SortColumn::Pid | SortColumn::Name => column.header().to_string(),
+        };
+        match (
+            column == process_table.sort_column,
+            process_table.sort_descending,
+        ) {
+            (true, true) => format!("{title} ▼").bold(),
+            (true, false) => format!("{title} ▲").bold(),
+            (false, _) => title.into(),
+        }
+    });
+
+    let table = Table::new(rows, PROCESS_TABLE_WIDTHS)
+        .header(Row::new(
+            sortable_header
+                .into_iter()
+                .chain(["send history".into(), "received history".into()]),
+        ))
+        .footer(unattributed)
+        .row_highlight_style(Style::new().reversed());
+    table
+}
+
+fn get_table_of_interface_statistics<'a>(state: &'a Model<'a>) -> Table<'a> {
+    let tracker = &state.bandwidth_tracker;
+    let rates = tracker.get_interface_rates(state.rate_window);
+    let history = tracker.get_interface_rates_history();
+
+    let rows: Vec<_> = state
+        .links
+        .iter()
+        .map(|(interface, link)| {
+            let rate = rates.get(interface).copied().unwrap_or_default();
+            let history = history
+                .get(interface)
+                .map(Vec::as_slice)
                 .unwrap_or_default();
+            let throughput_history = history
+                .iter()
+                .map(|rates| f64::from(rates.received) + f64::from(rates.send));
+            let problems_history = history.iter().map(|rates| rates.errors + rates.drops);
 
-            Some(Row::new([
-                pid.to_string(),
-                process_name.to_string(),
-                send.to_string(),
-                received.to_string(),
-                total_bytes_send.to_string(),
-                total_bytes_received.to_string(),
-            ]))
+            let row = Row::new([
+                interface.clone(),
+                link.operstate.clone(),
+                link.mtu.map(|mtu| mtu.to_string()).unwrap_or_default(),
+                link.speed
+                    .map(|speed| format!("{speed} Mbit/s"))
+                    .unwrap_or_default(),
+                link.carrier_changes
+                    .map(|changes| changes.to_string())
+                    .unwrap_or_default(),
+                state.units.rate(rate.received),
+                state.units.rate(rate.send),
+                format!("{:.1}", rate.received_packets),
+                format!("{:.1}", rate.send_packets),
+                format!("{:.1}", rate.errors),
+                format!("{:.1}", rate.drops),
+                format!("{:.1}", rate.fifo),
+                format!("{:.1}", rate.multicast),
+                sparkline(throughput_history, SPARKLINE_WIDTH),
+                sparkline(problems_history, SPARKLINE_WIDTH),
+            ]);
+            if state.alerts.is_firing_for_interface(interface) {
+                row.style(state.theme.alert)
+            } else if rate.errors > 0. || rate.drops > 0. || link.is_down() {
+                row.style(state.theme.problem)
+            } else {
+                row
+            }
         })
         .collect();
 
     let table_widths = [
-        Constraint::Percentage(15),
-        Constraint::Percentage(15),
-        Constraint::Percentage(15),
-        Constraint::Percentage(15),
-        Constraint::Percentage(15),
-        Constraint::Percentage(15),
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Fill(2),
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+        Constraint::Length(SPARKLINE_WIDTH as u16),
+        Constraint::Length(SPARKLINE_WIDTH as u16),
     ];
 
-    let table = Table::new(rows, table_widths).header(Row::new(vec![
-        "pid",
-        "name",
-        "bytes send/s",
-        "bytes received/s",
-        "total bytes send",
-        "total bytes received",
-    ]));
-    table
+    Table::new(rows, table_widths)
+        .header(Row::new(vec![
+            "interface",
+            "link",
+            "mtu",
+            "speed",
+            "flaps",
+            "rx/s",
+            "tx/s",
+            "rx pkt/s",
+            "tx pkt/s",
+            "errors/s",
+            "drops/s",
+            "fifo/s",
+            "mcast/s",
+            "throughput",
+            "errors+drops",
+        ]))
+        .block(Block::bordered().title(Line::from("Interfaces statistics").bold().centered()))
+}
+
+/// Points of the sparklines of the process table, only these are kept in the rows
+pub const SPARKLINE_WIDTH: usize = 16;
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Draws the last `width` values as a line of bars scaled on their maximum, zeros are left blank
+fn sparkline(values: impl DoubleEndedIterator<Item = f64>, width: usize) -> String {
+    let mut last_values: Vec<_> = values.rev().take(width).collect();
+    last_values.reverse();
+
+    let max = last_values.iter().copied().fold(0., f64::max);
+    last_values
+        .into_iter()
+        .map(|value| {
+            if value <= 0. || max <= 0. {
+                ' '
+            } else {
+                let bar = (value / max * (SPARKLINE_BARS.len() - 1) as f64).round() as usize;
+                SPARKLINE_BARS[bar]
+            }
+        })
+        .collect()
 }
 
 fn get_chart_of_global_thoughputs<'a>(state: &'a Model<'a>) -> Chart<'a> {
-    let datasets = state
+    let series = state
         .datasets
         .iter()
-        .zip([Color::LightBlue, Color::LightYellow])
-        .map(|((interface, points), color)| {
-            Dataset::default()
-                .name(interface.clone())
-                .data(points)
-                .graph_type(GraphType::Line)
-                .style(color)
+        .filter(|(interface, _)| state.interface_selection.is_charted(interface))
+        .flat_map(|(interface, points)| {
+            [
+                (format!("{interface} rx"), points.received.as_slice()),
+                (format!("{interface} tx"), points.send.as_slice()),
+            ]
         })
         .collect();
 
-    let y_max = state
-        .datasets
-        .iter()
-        .flat_map(|(_, v)| v.iter())
+    let title = match (state.chart_zoom, state.paused_at) {
+        (ChartZoom::Live, Some(_)) => "Network Interfaces (paused)".to_string(),
+        (ChartZoom::Live, None) => "Network Interfaces".to_string(),
+        (zoom, _) => format!("Network Interfaces ({zoom})"),
+    };
+    get_throughput_chart(title, series, &state.units, &state.theme).legend_position(None)
+}
+
+/// Legend of the interfaces chart drawn on its bottom border, a click on an interface toggles it
+fn draw_chart_legend(frame: &mut Frame, state: &mut Model, area: Rect) {
+    let mut colors = state.theme.chart.iter().copied().cycle();
+    let mut spans = Vec::new();
+    let mut x = area.x + 1;
+    let hide_idle = state.interface_selection.hide_idle;
+    for (interface, _, idle) in state.interface_selection.interfaces() {
+        if hide_idle && idle {
+            continue;
+        }
+        let entry = format!(" {interface} ");
+        let width = entry.chars().count() as u16 + 2;
+        if x + width >= area.right() {
+            break;
+        }
+
+        if state.interface_selection.is_charted(interface) {
+            // same colors as the rx and tx series of the chart
+            spans.push("▬".fg(colors.next().unwrap_or_default()));
+            spans.push("▬".fg(colors.next().unwrap_or_default()));
+            spans.push(entry.into());
+        } else {
+            spans.push("  ".into());
+            spans.push(entry.dim().crossed_out());
+        }
+        state.layout.legend.push((
+            Rect::new(x, area.bottom().saturating_sub(1), width, 1),
+            interface.clone(),
+        ));
+        x += width;
+    }
+
+    let legend_area = Rect::new(
+        area.x + 1,
+        area.bottom().saturating_sub(1),
+        area.width.saturating_sub(2),
+        1,
+    );
+    frame.render_widget(Line::from(spans), legend_area);
+}
+
+/// Line chart of named series of (seconds ago, bytes per second) points
+fn get_throughput_chart<'a>(
+    title: impl Into<Line<'a>>,
+    series: Vec<(String, &'a [(f64, f64)])>,
+    units: &UnitFormat,
+    theme: &Theme,
+) -> Chart<'a> {
+    let all_points = || series.iter().flat_map(|(_, points)| points.iter());
+
+    let y_max = all_points()
         .map(|item| item.1)
         .reduce(f64::max)
         .unwrap_or(0.);
+    let x_min = all_points()
+        .map(|item| item.0)
+        .reduce(f64::min)
+        .unwrap_or(0.);
 
-    let x_max = state
-        .datasets
-        .values()
-        .map(|v| v.len())
-        .max()
-        .unwrap_or_default()
-        .min(255) as f64;
+    let x_labels = [x_min, x_min / 2., 0.].map(|seconds| time_ago(seconds.abs()));
+    let y_labels = [0., y_max / 2., y_max]
+        .map(|bytes_per_second| units.rate(BytesPerSecond::from(bytes_per_second)));
+
+    let datasets = series
+        .into_iter()
+        .zip(theme.chart.iter().copied().cycle())
+        .map(|((name, points), color)| {
+            Dataset::default()
+                .name(name)
+                .data(points)
+                .graph_type(GraphType::Line)
+                .style(color)
+        })
+        .collect();
 
     Chart::new(datasets)
-        .block(Block::bordered().title(Line::from("Network Interfaces").bold().centered()))
-        .x_axis(Axis::default().title("X Axis").bounds([0.0, x_max]))
-        .y_axis(Axis::default().title("Y Axis").bounds([0.0, y_max]))
+        .block(Block::bordered().title(title.into().bold().centered()))
+        .x_axis(
+            Axis::default()
+                .title("ago")
+                .bounds([x_min, 0.0])
+                .labels(x_labels),
+        )
+        .y_axis(
+            Axis::default()
+                .title("throughput")
+                .bounds([0.0, y_max])
+                .labels(y_labels),
+        )
         .hidden_legend_constraints((Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)))
 }
+
+/// Seconds as the largest unit that fits, like `90s`, `15m`, `6h` or `12d`
+fn time_ago(seconds: f64) -> String {
+    match seconds {
+        _ if seconds < 120. => format!("{seconds:.0}s"),
+        _ if seconds < 2. * 3600. => format!("{:.0}m", seconds / 60.),
+        _ if seconds < 2. * 86400. => format!("{:.0}h", seconds / 3600.),
+        _ => format!("{:.0}d", seconds / 86400.),
+    }
+}
+
+fn draw_interface_picker(frame: &mut Frame, state: &Model, cursor: usize, area: Rect) {
+    let items: Vec<_> = state
+        .interface_selection
+        .interfaces()
+        .map(|(interface, visible, idle)| {
+            let checkbox = if visible { "[x]" } else { "[ ]" };
+            let idle = if idle { " (idle)" } else { "" };
+            ListItem::new(format!("{checkbox} {interface}{idle}"))
+        })
+        .collect();
+
+    let hide_idle = if state.interface_selection.hide_idle {
+        "idle hidden"
+    } else {
+        "idle shown"
+    };
+    let list = List::new(items)
+        .block(
+            Block::bordered()
+                .title(Line::from("Interfaces").bold().centered())
+                .title_bottom(Line::from(format!("space: toggle, z: {hide_idle}")).centered()),
+        )
+        .highlight_symbol("> ")
+        .highlight_style(Style::new().reversed());
+
+    let [popup] = Layout::horizontal([Constraint::Percentage(40)])
+        .flex(Flex::Center)
+        .areas(area);
+    let mut list_state = ListState::default().with_selected(Some(cursor));
+    frame.render_widget(Clear, popup);
+    frame.render_stateful_widget(list, popup, &mut list_state);
+}
+
+fn draw_process_detail(frame: &mut Frame, state: &Model, pane: &ProcessDetailPane, area: Rect) {
+    let details = &pane.details;
+    let tracker = &state.bandwidth_tracker;
+
+    let [top, bottom] = Layout::vertical([Constraint::Fill(1); 2]).areas(area);
+    let [informations_area, sockets_area] =
+        Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(top);
+
+    let total_received = tracker
+        .get_nbr_of_bytes_received_since_monitoring_started(details.pid)
+        .unwrap_or_default();
+    let total_send = tracker
+        .get_nbr_of_bytes_send_since_monitoring_started(details.pid)
+        .unwrap_or_default();
+    let (peak_received, peak_send) = tracker
+        .get_peak_throughput_of_pid(details.pid)
+        .unwrap_or_default();
+
+    let field = |name: &'static str, value: String| {
+        Line::from(vec![format!("{name:<10}").bold(), value.into()])
+    };
+    let mut lines = vec![
+        field("pid", details.pid.to_string()),
+        field("name", details.name.clone()),
+        field("user", details.user.clone()),
+        field(
+            "exe",
+            details
+                .exe
+                .as_ref()
+                .map(|exe| exe.display().to_string())
+                .unwrap_or_default(),
+        ),
+        field("cmdline", details.cmdline.clone()),
+        field("started", details.start_time.clone().unwrap_or_default()),
+        field(
+            "send",
+            format!(
+                "{} (peak {})",
+                state.units.bytes(total_send),
+                state.units.rate(peak_send)
+            ),
+        ),
+        field(
+            "received",
+            format!(
+                "{} (peak {})",
+                state.units.bytes(total_received),
+                state.units.rate(peak_received)
+            ),
+        ),
+    ];
+    lines.extend(
+        details
+            .cgroups
+            .iter()
+            .map(|cgroup| field("cgroup", cgroup.clone())),
+    );
+
+    let informations = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::bordered()
+            .title(Line::from(details.name.as_str()).bold().centered())
+            .title_bottom(Line::from("esc: back").centered()),
+    );
+
+    let sockets: Vec<_> = details
+        .sockets
+        .iter()
+        .map(|socket| {
+            Row::new([
+                socket.protocol.to_string(),
+                socket.local.to_string(),
+                socket.remote.to_string(),
+                socket.state.clone(),
+            ])
+        })
+        .collect();
+    let sockets = Table::new(
+        sockets,
+        [
+            Constraint::Length(5),
+            Constraint::Fill(2),
+            Constraint::Fill(2),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(Row::new(["proto", "local", "remote", "state"]))
+    .block(Block::bordered().title(Line::from("Sockets").bold().centered()));
+
+    let chart = get_throughput_chart(
+        "Throughput",
+        vec![
+            ("rx".to_string(), pane.datasets.received.as_slice()),
+            ("tx".to_string(), pane.datasets.send.as_slice()),
+        ],
+        &state.units,
+        &state.theme,
+    );
+
+    frame.render_widget(informations, informations_area);
+    frame.render_widget(sockets, sockets_area);
+    frame.render_widget(chart, bottom);
+}
+
+fn draw_dialog(frame: &mut Frame, dialog: &Dialog, area: Rect) {
+    let (title, body, height): (String, Vec<Line>, u16) = match dialog {
+        Dialog::SignalPicker {
+            pid, name, cursor, ..
+        } => (
+            format!("Signal {name} ({pid})"),
+            SIGNALS
+                .iter()
+                .enumerate()
+                .map(|(index, (signal, _))| {
+                    if index == *cursor {
+                        Line::from(format!("> {signal}")).reversed()
+                    } else {
+                        Line::from(format!("  {signal}"))
+                    }
+                })
+                .collect(),
+            SIGNALS.len() as u16,
+        ),
+        Dialog::Renice {
+            pid, name, nice, ..
+        } => (
+            format!("Renice {name} ({pid})"),
+            vec![
+                Line::from(format!("nice: {nice}")).centered(),
+                Line::from("↑/↓: change").centered().dim(),
+            ],
+            2,
+        ),
+        Dialog::Confirm {
+            pid, name, action, ..
+        } => (
+            "Confirm".to_string(),
+            vec![Line::from(format!("{} {name} ({pid})?", action.describe())).centered()],
+            1,
+        ),
+    };
+
+    let block = Block::bordered()
+        .title(Line::from(title).bold().centered())
+        .title_bottom(Line::from("enter: ok, esc: cancel").centered());
+    let [popup] = Layout::horizontal([Constraint::Percentage(40)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Length(height + 2)])
+        .flex(Flex::Center)
+        .areas(popup);
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(Paragraph::new(body).block(block), popup);
+}
+
+fn draw_help(frame: &mut Frame, keymap: &Keymap, area: Rect) {
+    let help: Vec<_> = keymap.help().collect();
+    let keys_width = help.iter().map(|(keys, _)| keys.chars().count()).max();
+    let rows = help.into_iter().map(|(keys, description)| {
+        Row::new(vec![Line::from(keys).bold(), Line::from(description)])
+    });
+    let height = rows.len() as u16;
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(keys_width.unwrap_or_default() as u16),
+            Constraint::Fill(1),
+        ],
+    )
+    .block(
+        Block::bordered()
+            .title(Line::from("Keybindings").bold().centered())
+            .title_bottom(
+                Line::from(format!("{}: close", keymap.keys_of(Event::ToggleHelp))).centered(),
+            ),
+    );
+
+    let [popup] = Layout::horizontal([Constraint::Percentage(60)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [popup] = Layout::vertical([Constraint::Length(height + 2)])
+        .flex(Flex::Center)
+        .areas(popup);
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(table, popup);
+}
+
+#[cfg(test)]
+mod tests_render {
+    use super::sparkline;
+
+    #[test]
+    fn test_sparkline_keeps_the_last_values() {
+        assert_eq!(sparkline([8., 0., 1., 4., 8.].into_iter(), 4), " ▂▅█");
+        assert_eq!(sparkline([0., 0.].into_iter(), 4), "  ");
+    }
+}
@@ -1,22 +1,156 @@
 use std::{
     collections::{BTreeMap, HashMap},
     error::Error,
-    time::Duration,
+    time::{Duration, Instant, SystemTime},
 };
 
-use libbpf_rs::Map;
 use procfs::process::Process;
+use ratatui::layout::{Position, Rect};
 
-use crate::bandwidth_tracker::tracker::BandwidthTracker;
+use crate::{
+    bandwidth_tracker::{
+        alerts::AlertMonitor,
+        bytes::{BytesPerSecond, UnitFormat},
+        measure::{RateWindow, TotalsSince},
+        quotas::UsageAccounting,
+        source::CounterSource,
+        store::{ChartZoom, HistoryStore},
+        tracker::BandwidthTracker,
+    },
+    config::{ChartPlacement, Config},
+    export::UsageExport,
+    system::{
+        link::LinkInfo,
+        process::{renice, send_signal, start_time_of, ProcessDetails, ProcessIdentity, SIGNALS},
+    },
+};
+
+use super::{
+    dialog::{Dialog, DialogStep, ProcessAction},
+    events::Event,
+    filter::Filter,
+    interface_selection::InterfaceSelection,
+    keymap::Keymap,
+    process_table::{ProcessRow, ProcessTable, SortColumn},
+    render::SPARKLINE_WIDTH,
+    tabs::{
+        connections::ConnectionsTab, hosts::HostsTab, log::Log, navigate, usage::UsageTab, Tab,
+    },
+    theme::Theme,
+};
+
+/// Chart points of an interface or a process, `x` is the number of seconds ago (negative) and `y` the bytes per second
+#[derive(Default)]
+pub struct ThroughputDatasets {
+    pub received: Vec<(f64, f64)>,
+    pub send: Vec<(f64, f64)>,
+}
+
+impl ThroughputDatasets {
+    fn new(history: &[(SystemTime, BytesPerSecond, BytesPerSecond)], now: SystemTime) -> Self {
+        let seconds_ago =
+            |at: &SystemTime| -now.duration_since(*at).unwrap_or_default().as_secs_f64();
+        ThroughputDatasets {
+            received: history
+                .iter()
+                .map(|(at, received, _)| (seconds_ago(at), f64::from(*received)))
+                .collect(),
+            send: history
+                .iter()
+                .map(|(at, _, send)| (seconds_ago(at), f64::from(*send)))
+                .collect(),
+        }
+    }
+}
+
+pub struct ProcessDetailPane {
+    pub details: ProcessDetails,
+    pub datasets: ThroughputDatasets,
+    /// When the details were read, they are re-read every `PROCESS_DETAIL_REFRESH`
+    read_at: Instant,
+}
+
+/// Areas of the last drawn frame, to find what is under the mouse
+#[derive(Default)]
+pub struct ScreenLayout {
+    /// Title of each tab in the tab bar
+    pub tabs: Vec<(Rect, Tab)>,
+    /// Header cell of each column of the process table
+    pub table_header: Vec<(Rect, SortColumn)>,
+    /// Rows of the process table, without its header and footer
+    pub table_rows: Rect,
+    /// Entry of each interface in the legend of the chart
+    pub legend: Vec<(Rect, String)>,
+}
+
+/// Rows moved by one step of the mouse wheel
+const SCROLL_STEP: isize = 3;
 
-use super::events::Event;
+/// Seconds moved by one seek in a replay
+const SEEK_STEP: i64 = 10;
+
+/// The zoomed chart is read from the history at this interval instead of every tick
+const ZOOMED_CHART_REFRESH: Duration = Duration::from_secs(5);
+/// Reading the sockets of a process is costly, the detail pane is not re-read on every tick
+const PROCESS_DETAIL_REFRESH: Duration = Duration::from_secs(1);
+/// The link properties of the interfaces rarely change, `/sys` is not read on every tick
+const LINK_INFO_REFRESH: Duration = Duration::from_secs(5);
+/// Reading the identity of a process reads its cgroup, only the new processes are read on every tick
+const PROCESS_IDENTITY_REFRESH: Duration = Duration::from_secs(5);
 
 pub struct Model<'a> {
     pub process_by_pid: HashMap<i32, Process>,
+    /// Identity of the tracked processes, including the ones that exited since
+    process_identities: HashMap<i32, ProcessIdentity>,
+    /// When the identities of every running process were last read, to follow the renamed and exec'd processes
+    identities_read_at: Option<Instant>,
     pub bandwidth_tracker: BandwidthTracker,
-    pub datasets: BTreeMap<String, Vec<(f64, f64)>>,
+    pub datasets: BTreeMap<String, ThroughputDatasets>,
+    pub interface_selection: InterfaceSelection,
+    pub links: BTreeMap<String, LinkInfo>,
+    /// When the link properties were last read
+    links_read_at: Option<Instant>,
+    pub tab: Tab,
+    pub chart_placement: ChartPlacement,
+    pub connections: ConnectionsTab,
+    pub hosts: HostsTab,
+    pub log: Log,
+    pub usage: UsageTab,
+    pub process_table: ProcessTable,
+    /// Text of the filter of the process table, kept across ticks
+    pub filter_text: String,
+    pub filter: Result<Filter, String>,
+    editing_filter: bool,
+    /// Tick shown by the process table when the display is paused, `None` when following the live data
+    pub paused_at: Option<SystemTime>,
+    pub dialog: Option<Dialog>,
+    /// Outcome of the last action, shown in the status line
+    pub status_message: Option<String>,
+    pub keymap: Keymap,
+    pub units: UnitFormat,
+    pub theme: Theme,
+    /// Rates above which the process rows are colored
+    pub heat_thresholds: Vec<BytesPerSecond>,
+    pub alerts: AlertMonitor,
+    /// Long-term history, only kept for live sources
+    history: Option<HistoryStore>,
+    pub chart_zoom: ChartZoom,
+    /// When the zoomed chart was last read from the history
+    zoomed_at: Option<Instant>,
+    pub accounting: UsageAccounting,
+    /// Usage rows written for other tools
+    export: Option<UsageExport>,
+    /// Last failure of the export, fatal without the TUI
+    export_error: Option<String>,
+    /// Window of the throughputs of the tables
+    pub rate_window: RateWindow,
+    pub totals_since: TotalsSince,
+    /// Show the overlay listing the keybindings
+    pub show_help: bool,
+    pub layout: ScreenLayout,
+    pub process_detail: Option<ProcessDetailPane>,
     pub refresh_rate: Duration,
-    packet_stats: &'a Map<'a>,
+    source: Box<dyn CounterSource + 'a>,
 }
 
 fn get_process_data_by_pid() -> HashMap<i32, Process> {
@@ -28,44 +162,680 @@ fn get_process_data_by_pid() -> HashMap<i32, Process> {
 
 impl<'a> Model<'a> {
     pub fn init(
-        packet_stats: &'a Map,
+        source: Box<dyn CounterSource + 'a>,
         refresh_rate: Duration,
+        config: Config,
     ) -> Result<Model<'a>, Box<dyn Error>> {
-        let process_by_pid = get_process_data_by_pid();
+        let process_by_pid = match source.is_live() {
+            true => get_process_data_by_pid(),
+            false => HashMap::new(),
+        };
         let bandwidth_tracker = BandwidthTracker::new();
+        let keymap = Keymap::new(&config.keybindings)?;
+        let units = config.display.unit_format()?;
+        let heat_thresholds = config.display.heat_thresholds()?;
+        let history = match source.is_live() {
+            true => HistoryStore::open(&config.history)?,
+            false => None,
+        };
+        let export = UsageExport::open(&config.export)?;
+        let mut alerts = config.alerts;
+        // a replay shows the alerts of the recording without running their commands again
+        if !source.is_live() {
+            alerts.hook = None;
+        }
 
         Ok(Model {
             process_by_pid,
+            process_identities: HashMap::new(),
+            identities_read_at: None,
             bandwidth_tracker,
-            packet_stats,
+            source,
             datasets: BTreeMap::new(),
+            interface_selection: InterfaceSelection::new(config.interfaces),
+            links: BTreeMap::new(),
+            links_read_at: None,
+            tab: Tab::default(),
+            chart_placement: config.display.chart,
+            connections: ConnectionsTab::default(),
+            hosts: HostsTab::default(),
+            log: Log::default(),
+            usage: UsageTab::default(),
+            process_table: ProcessTable::default(),
+            filter_text: String::new(),
+            filter: Ok(Filter::default()),
+            editing_filter: false,
+            paused_at: None,
+            dialog: None,
+            status_message: None,
+            keymap,
+            units,
+            theme: Theme::new(config.display.theme),
+            heat_thresholds,
+            alerts: AlertMonitor::new(alerts),
+            history,
+            chart_zoom: ChartZoom::default(),
+            zoomed_at: None,
+            accounting: UsageAccounting::new(config.quotas)?,
+            export,
+            export_error: None,
+            rate_window: config.display.rate_window,
+            totals_since: TotalsSince::Start,
+            show_help: false,
+            layout: ScreenLayout::default(),
+            process_detail: None,
             refresh_rate,
         })
     }
 
     pub fn handel_event(mut self, event: &Event) -> Result<Model<'a>, Box<dyn Error>> {
-        if event != &Event::Tick {
-            return Err("Event not handeld".to_string().into());
+        if self.show_help && event != &Event::Tick {
+            if matches!(event, Event::ToggleHelp | Event::Back | Event::Select) {
+                self.show_help = false;
+            }
+            return Ok(self);
+        }
+        if self.dialog.is_some() && event != &Event::Tick {
+            self.handle_dialog_event(event);
+            return Ok(self);
+        }
+
+        let picker_is_open = self.interface_selection.picker_cursor.is_some();
+        match event {
+            Event::Tick => self.refresh(),
+            Event::SignalProcess | Event::ReniceProcess if !self.source.is_live() => {
+                self.status_message = Some("the processes of a replay cannot be acted on".into())
+            }
+            Event::SignalProcess => {
+                if let Some((pid, start_time, name)) = self.action_target() {
+                    self.dialog = Some(Dialog::SignalPicker {
+                        pid,
+                        start_time,
+                        name,
+                        cursor: 0,
+                    });
+                }
+            }
+            Event::ReniceProcess => {
+                if let Some((pid, start_time, name)) = self.action_target() {
+                    let nice = self
+                        .process_by_pid
+                        .get(&pid)
+                        .and_then(|process| process.stat().ok())
+                        .map(|stat| stat.nice as i32)
+                        .unwrap_or_default();
+                    self.dialog = Some(Dialog::Renice {
+                        pid,
+                        start_time,
+                        name,
+                        nice,
+                    });
+                }
+            }
+            Event::Input(c) if self.editing_filter => {
+                self.filter_text.push(*c);
+                self.apply_filter();
+            }
+            Event::DeleteChar if self.editing_filter => {
+                self.filter_text.pop();
+                self.apply_filter();
+            }
+            Event::Select if self.editing_filter => self.editing_filter = false,
+            Event::Back if self.editing_filter => {
+                self.editing_filter = false;
+                self.filter_text.clear();
+                self.apply_filter();
+            }
+            Event::Input(_) | Event::DeleteChar => {}
+            Event::StartSearch => self.editing_filter = true,
+            Event::TogglePause => {
+                self.paused_at = match self.paused_at {
+                    Some(_) => None,
+                    None => Some(self.bandwidth_tracker.last_tick()),
+                };
+                self.source.set_paused(self.paused_at.is_some());
+                self.process_table.set_rows(self.process_rows());
+            }
+            Event::ReplaySlower => self.source.change_speed(false),
+            Event::ReplayFaster => self.source.change_speed(true),
+            Event::SeekBack => self.seek(-SEEK_STEP),
+            Event::SeekForward => self.seek(SEEK_STEP),
+            Event::ZoomOut => self.zoom_chart(true),
+            Event::ZoomIn => self.zoom_chart(false),
+            Event::StepBack => {
+                let view_time = self.view_time();
+                self.paused_at = Some(
+                    self.bandwidth_tracker
+                        .previous_tick(view_time)
+                        .unwrap_or(view_time),
+                );
+                self.process_table.set_rows(self.process_rows());
+            }
+            Event::StepForward => {
+                if let Some(paused_at) = self.paused_at {
+                    self.paused_at = Some(
+                        self.bandwidth_tracker
+                            .next_tick(paused_at)
+                            .unwrap_or(paused_at),
+                    );
+                    self.process_table.set_rows(self.process_rows());
+                }
+            }
+            Event::ToggleHelp => self.show_help = true,
+            Event::Click(column, row) => self.click(Position::new(*column, *row)),
+            Event::ScrollUp if picker_is_open => self.interface_selection.move_cursor(-1),
+            Event::ScrollDown if picker_is_open => self.interface_selection.move_cursor(1),
+            Event::Quit => return Err("Event not handeld".to_string().into()),
+            Event::ToggleInterfacePicker => self.interface_selection.toggle_picker(),
+            Event::SelectTab(tab) => self.select_tab(*tab),
+            Event::NextTab => self.select_tab(self.tab.cycle(1)),
+            Event::PreviousTab => self.select_tab(self.tab.cycle(-1)),
+            Event::ToggleBits => {
+                // a fixed unit decides between bits and bytes, it is dropped to let the toggle work
+                self.units.bits = !self
+                    .units
+                    .fixed_unit
+                    .map_or(self.units.bits, |unit| unit.is_bit());
+                self.units.fixed_unit = None;
+            }
+            Event::CycleRateWindow => {
+                self.rate_window = self.rate_window.next();
+                self.process_table.set_rows(self.process_rows());
+            }
+            Event::ToggleTotals => {
+                self.totals_since = match self.totals_since {
+                    TotalsSince::Start => TotalsSince::Reset,
+                    TotalsSince::Reset => TotalsSince::Start,
+                };
+                self.process_table.set_rows(self.process_rows());
+            }
+            Event::ResetTotals => {
+                self.bandwidth_tracker.reset_totals();
+                self.totals_since = TotalsSince::Reset;
+                self.process_table.set_rows(self.process_rows());
+            }
+            Event::ToggleIdleInterfaces => {
+                self.interface_selection.hide_idle = !self.interface_selection.hide_idle
+            }
+            Event::Up if picker_is_open => self.interface_selection.move_cursor(-1),
+            Event::Down if picker_is_open => self.interface_selection.move_cursor(1),
+            Event::Select if picker_is_open => self.interface_selection.toggle_under_cursor(),
+            Event::Back if picker_is_open => self.interface_selection.toggle_picker(),
+            Event::Up
+            | Event::Down
+            | Event::PageUp
+            | Event::PageDown
+            | Event::Home
+            | Event::End
+            | Event::Select
+            | Event::Back
+            | Event::ScrollUp
+            | Event::ScrollDown
+                if self.tab != Tab::Processes =>
+            {
+                self.navigate_tab(event)
+            }
+            Event::Back if self.process_detail.is_some() => self.process_detail = None,
+            Event::ScrollUp => self.process_table.move_selection(-SCROLL_STEP),
+            Event::ScrollDown => self.process_table.move_selection(SCROLL_STEP),
+            Event::Up => self.process_table.move_selection(-1),
+            Event::Down => self.process_table.move_selection(1),
+            Event::PageUp => self.process_table.page_up(),
+            Event::PageDown => self.process_table.page_down(),
+            Event::Home => self.process_table.select_first(),
+            Event::End => self.process_table.select_last(),
+            Event::Select => self.open_process_detail(),
+            Event::Back => self.process_table.unselect(),
+        }
+        Ok(self)
+    }
+
+    /// Recording or position in the replay
+    pub fn source_status(&self) -> Option<String> {
+        self.source.status()
+    }
+
+    /// The traffic of the processes is estimated, not measured by the probes
+    pub fn is_approximate(&self) -> bool {
+        self.source.is_approximate()
+    }
+
+    pub fn is_editing_filter(&self) -> bool {
+        self.editing_filter
+    }
+
+    pub fn take_export_error(&mut self) -> Option<String> {
+        self.export_error.take()
+    }
+
+    /// Identity of a tracked process, also once it exited
+    pub fn process_identity(&self, pid: i32) -> Option<&ProcessIdentity> {
+        self.process_identities.get(&pid)
+    }
+
+    fn select_tab(&mut self, tab: Tab) {
+        self.tab = tab;
+        if matches!(tab, Tab::Connections | Tab::Hosts) {
+            self.refresh_connections();
+        }
+        if tab == Tab::Usage {
+            self.usage.refresh(&self.accounting);
+        }
+    }
+
+    /// Navigation in the table of the current tab, the process table has its own cursor
+    fn navigate_tab(&mut self, event: &Event) {
+        match self.tab {
+            Tab::Connections => {
+                let tab = &mut self.connections;
+                navigate(&mut tab.state, event, tab.rows.len(), tab.page_size);
+            }
+            Tab::Hosts => {
+                let tab = &mut self.hosts;
+                navigate(&mut tab.state, event, tab.rows.len(), tab.page_size);
+            }
+            Tab::Log => {
+                let tab = &mut self.log;
+                navigate(&mut tab.state, event, tab.entries.len(), tab.page_size);
+            }
+            Tab::Usage => {
+                let tab = &mut self.usage;
+                navigate(&mut tab.state, event, tab.rows.len(), tab.page_size);
+            }
+            Tab::Processes | Tab::Interfaces => {}
+        }
+    }
+
+    fn refresh_connections(&mut self) {
+        let processes = self
+            .process_identities
+            .iter()
+            .filter_map(|(pid, identity)| {
+                let process = self.process_by_pid.get(pid)?;
+                Some((*pid, identity.name.clone(), process))
+            });
+        let tracker = &self.bandwidth_tracker;
+        self.connections
+            .refresh(processes, |inode| tracker.get_bytes_of_connection(inode));
+        self.hosts.refresh(&self.connections.rows);
+    }
+
+    /// Hit-test the click against the areas of the last drawn frame
+    fn click(&mut self, position: Position) {
+        if let Some((_, tab)) = self
+            .layout
+            .tabs
+            .iter()
+            .find(|(area, _)| area.contains(position))
+        {
+            self.select_tab(*tab);
+        } else if let Some((_, column)) = self
+            .layout
+            .table_header
+            .iter()
+            .find(|(area, _)| area.contains(position))
+        {
+            self.process_table.sort_by(*column);
+        } else if self.layout.table_rows.contains(position) {
+            let visible_row = position.y - self.layout.table_rows.y;
+            self.process_table.select_visible(visible_row as usize);
+        } else if let Some((_, interface)) = self
+            .layout
+            .legend
+            .iter()
+            .find(|(area, _)| area.contains(position))
+        {
+            self.interface_selection.toggle(interface);
         }
+    }
 
-        self.bandwidth_tracker.refresh_tick(self.packet_stats);
-        self.process_by_pid = get_process_data_by_pid();
+    /// (pid, name) of the process under the cursor of the process table
+    fn selected_process(&self) -> Option<(i32, String)> {
+        let pid = self.process_table.selected_pid()?;
+        let row = self.process_table.rows.iter().find(|row| row.pid == pid)?;
+        Some((pid, row.name.clone()))
+    }
 
-        self.datasets = self
+    /// (pid, start time, name) of the selected process when it is still running
+    ///
+    /// A paused or past view lists processes that exited since, they cannot be acted on.
+    fn action_target(&mut self) -> Option<(i32, u64, String)> {
+        let (pid, name) = self.selected_process()?;
+        let start_time = self
             .bandwidth_tracker
-            .get_throughput_over_duration_per_interface()
-            .into_iter()
-            .map(|(key, values)| {
-                (
-                    key,
-                    values
-                        .iter()
-                        .enumerate()
-                        .map(|(n, v)| (n as f64, f64::from(*v)))
-                        .collect(),
-                )
+            .was_tracked_at(pid, self.bandwidth_tracker.last_tick())
+            .then(|| start_time_of(pid))
+            .flatten();
+        if start_time.is_none() {
+            self.report(format!("{name} ({pid}) has exited"));
+        }
+        Some((pid, start_time?, name))
+    }
+
+    fn handle_dialog_event(&mut self, event: &Event) {
+        let Some(dialog) = self.dialog.as_mut() else {
+            return;
+        };
+
+        match event {
+            Event::Up | Event::ScrollUp => dialog.move_cursor(-1),
+            Event::Down | Event::ScrollDown => dialog.move_cursor(1),
+            Event::Back => self.dialog = None,
+            Event::Select => {
+                let Some(dialog) = self.dialog.take() else {
+                    return;
+                };
+                match dialog.validate() {
+                    DialogStep::Next(dialog) => self.dialog = Some(dialog),
+                    DialogStep::Confirmed(pid, start_time, action) => {
+                        self.apply_action(pid, start_time, action)
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Acts on the process only when its pid was not reused since the dialog was opened
+    fn apply_action(&mut self, pid: i32, start_time: u64, action: ProcessAction) {
+        if start_time_of(pid) != Some(start_time) {
+            self.report(format!(
+                "{} {pid}: the process has exited",
+                action.describe()
+            ));
+            return;
+        }
+        let result = match action {
+            ProcessAction::Signal(index) => send_signal(pid, SIGNALS[index].1),
+            ProcessAction::Renice(nice) => renice(pid, nice),
+        };
+        let message = match result {
+            Ok(()) => format!("{} {pid}: done", action.describe()),
+            Err(error) => format!("{} {pid}: {error}", action.describe()),
+        };
+        self.report(message);
+    }
+
+    /// Shows the message in the status line and keeps it in the log
+    pub fn report(&mut self, message: String) {
+        self.log.push(message.clone());
+        self.status_message = Some(message);
+    }
+
+    /// Time of the tick shown by the process table
+    pub fn view_time(&self) -> SystemTime {
+        self.paused_at.unwrap_or(self.bandwidth_tracker.last_tick())
+    }
+
+    fn apply_filter(&mut self) {
+        self.filter = Filter::parse(&self.filter_text);
+        self.process_table.set_rows(self.process_rows());
+    }
+
+    /// Rows of the process table at the viewed tick matching the filter
+    fn process_rows(&self) -> Vec<ProcessRow> {
+        let tracker = &self.bandwidth_tracker;
+        let view_time = self.view_time();
+        tracker
+            .get_throughput_at(view_time, self.rate_window)
+            .filter_map(|(pid, received, send)| {
+                let identity = self.process_identities.get(&pid)?.clone();
+                let (total_received, total_send) = tracker
+                    .get_nbr_of_bytes_at(pid, view_time, self.totals_since)
+                    .unwrap_or_default();
+                Some(ProcessRow {
+                    pid,
+                    name: identity.name,
+                    cmdline: identity.cmdline,
+                    user: identity.user,
+                    received,
+                    send,
+                    total_received,
+                    total_send,
+                    history: tracker.get_recent_throughputs_of_pid_at(
+                        pid,
+                        view_time,
+                        SPARKLINE_WIDTH,
+                    ),
+                })
             })
+            // an invalid filter is reported in the status line and shows every process
+            .filter(|row| {
+                self.filter
+                    .as_ref()
+                    .map_or(true, |filter| filter.matches(row))
+            })
+            .collect()
+    }
+
+    fn open_process_detail(&mut self) {
+        if let Some(pid) = self.process_table.selected_pid() {
+            self.process_detail = self.read_process_detail(pid);
+        }
+    }
+
+    fn read_process_detail(&self, pid: i32) -> Option<ProcessDetailPane> {
+        let process = self.process_by_pid.get(&pid)?;
+        let history = self.bandwidth_tracker.get_throughput_history_of_pid(pid);
+        Some(ProcessDetailPane {
+            details: ProcessDetails::read(process),
+            datasets: ThroughputDatasets::new(&history, self.bandwidth_tracker.last_tick()),
+            read_at: Instant::now(),
+        })
+    }
+
+    /// The chart of the detail pane follows every tick, the details are re-read every `PROCESS_DETAIL_REFRESH`
+    fn refresh_process_detail(&mut self) {
+        let Some(pane) = self.process_detail.as_mut() else {
+            return;
+        };
+        let pid = pane.details.pid;
+        if pane.read_at.elapsed() < PROCESS_DETAIL_REFRESH {
+            let history = self.bandwidth_tracker.get_throughput_history_of_pid(pid);
+            pane.datasets = ThroughputDatasets::new(&history, self.bandwidth_tracker.last_tick());
+            return;
+        }
+        // keep showing the last known details once the process is gone
+        if let Some(pane) = self.read_process_detail(pid) {
+            self.process_detail = Some(pane);
+        }
+    }
+
+    /// Rebuilds the histories from the new position of the replay
+    fn seek(&mut self, seconds: i64) {
+        if !self.source.seek(seconds) {
+            return;
+        }
+        self.bandwidth_tracker = BandwidthTracker::new();
+        // the replayed backfill was already exported, or is skipped
+        let export = self.export.take();
+        self.refresh();
+        self.export = export;
+        if self.paused_at.is_some() {
+            self.paused_at = Some(self.bandwidth_tracker.last_tick());
+            self.process_table.set_rows(self.process_rows());
+        }
+    }
+
+    fn refresh(&mut self) {
+        let snapshots = self.source.poll().unwrap_or_else(|error| {
+            self.status_message = Some(error.to_string());
+            Vec::new()
+        });
+        for snapshot in &snapshots {
+            self.bandwidth_tracker.ingest(snapshot);
+            self.process_identities
+                .extend(snapshot.identities.iter().cloned());
+            self.export_usage();
+        }
+        if self.source.is_live() {
+            self.process_by_pid = get_process_data_by_pid();
+            self.refresh_process_identities();
+            self.evaluate_alerts();
+            // replayed traffic is not added to the usage of this host
+            self.account_usage();
+            self.record_history();
+        } else {
+            let tracker = &self.bandwidth_tracker;
+            self.process_identities
+                .retain(|pid, _| tracker.is_tracked(*pid));
+            self.evaluate_alerts();
+        }
+        if matches!(self.tab, Tab::Connections | Tab::Hosts) {
+            self.refresh_connections();
+        }
+
+        if let Some(paused_at) = self.paused_at {
+            // sampling continues while paused, the viewed tick can fall out of the history
+            self.paused_at = Some(paused_at.max(self.bandwidth_tracker.oldest_tick()));
+            self.process_table.set_rows(self.process_rows());
+            return;
+        }
+
+        self.refresh_datasets();
+
+        self.refresh_process_detail();
+        self.process_table.set_rows(self.process_rows());
+    }
+
+    /// Series of the interfaces chart, from the history when the chart is zoomed out
+    fn refresh_datasets(&mut self) {
+        let now = self.bandwidth_tracker.last_tick();
+        match (self.chart_zoom.span(), &self.history) {
+            (Some(span), Some(history)) => {
+                if self
+                    .zoomed_at
+                    .is_some_and(|zoomed_at| zoomed_at.elapsed() < ZOOMED_CHART_REFRESH)
+                {
+                    return;
+                }
+                self.zoomed_at = Some(Instant::now());
+                match history.interface_rates(span, now) {
+                    Ok(rates) => {
+                        self.datasets = rates
+                            .into_iter()
+                            .map(|(interface, rates)| {
+                                (interface, ThroughputDatasets::new(&rates, now))
+                            })
+                            .collect()
+                    }
+                    Err(error) => self.status_message = Some(format!("history: {error}")),
+                }
+            }
+            _ => {
+                self.datasets = self
+                    .bandwidth_tracker
+                    .get_throughput_over_duration_per_interface()
+                    .into_iter()
+                    .map(|(interface, history)| (interface, ThroughputDatasets::new(&history, now)))
+                    .collect()
+            }
+        }
+        self.refresh_links();
+        self.interface_selection
+            .refresh(&self.datasets, &self.links);
+    }
+
+    /// Reads the link properties every `LINK_INFO_REFRESH`, or as soon as the interfaces change
+    fn refresh_links(&mut self) {
+        let same_interfaces = self.links.keys().eq(self.datasets.keys());
+        if same_interfaces
+            && self
+                .links_read_at
+                .is_some_and(|read_at| read_at.elapsed() < LINK_INFO_REFRESH)
+        {
+            return;
+        }
+        self.links = self
+            .datasets
+            .keys()
+            .map(|interface| (interface.clone(), LinkInfo::read(interface)))
             .collect();
-        Ok(self)
+        self.links_read_at = Some(Instant::now());
+    }
+
+    fn zoom_chart(&mut self, zoom_out: bool) {
+        if self.history.is_none() {
+            self.status_message = Some("the long-term history is off".into());
+            return;
+        }
+        self.chart_zoom = self.chart_zoom.zoom(zoom_out);
+        self.zoomed_at = None;
+        self.refresh_datasets();
+    }
+
+    fn record_history(&mut self) {
+        let Some(history) = self.history.as_mut() else {
+            return;
+        };
+        let identities = &self.process_identities;
+        let recorded = history.record(&self.bandwidth_tracker, |pid| {
+            identities.get(&pid).map(|identity| identity.name.as_str())
+        });
+        if let Err(error) = recorded {
+            self.status_message = Some(format!("history: {error}"));
+        }
+    }
+
+    fn export_usage(&mut self) {
+        let Some(export) = self.export.as_mut() else {
+            return;
+        };
+        let identities = &self.process_identities;
+        let exported = export.record(&self.bandwidth_tracker, |pid| identities.get(&pid).cloned());
+        if let Err(error) = exported {
+            self.status_message = Some(error.clone());
+            self.export_error = Some(error);
+        }
+    }
+
+    fn evaluate_alerts(&mut self) {
+        let identities = &self.process_identities;
+        let alerts = self.alerts.evaluate(&self.bandwidth_tracker, |pid| {
+            identities.get(&pid).map(|identity| identity.name.as_str())
+        });
+        for alert in alerts {
+            let message = alert.to_string();
+            self.log.push(message.clone());
+            self.status_message = Some(message);
+        }
+    }
+
+    fn account_usage(&mut self) {
+        let identities = &self.process_identities;
+        let warnings = self.accounting.account(&self.bandwidth_tracker, |pid| {
+            let identity = identities.get(&pid)?;
+            Some((identity.name.as_str(), identity.user.as_str()))
+        });
+        for warning in warnings {
+            let message = warning.to_string();
+            self.log.push(message.clone());
+            self.status_message = Some(message);
+        }
+        if self.tab == Tab::Usage {
+            self.usage.refresh(&self.accounting);
+        }
+    }
+
+    /// Reads the identity of the new processes, and of every running one every `PROCESS_IDENTITY_REFRESH`
+    fn refresh_process_identities(&mut self) {
+        let tracker = &self.bandwidth_tracker;
+        self.process_identities
+            .retain(|pid, _| tracker.is_tracked(*pid));
+
+        let read_all = self
+            .identities_read_at
+            .is_none_or(|read_at| read_at.elapsed() >= PROCESS_IDENTITY_REFRESH);
+        if read_all {
+            self.identities_read_at = Some(Instant::now());
+        }
+        for (pid, process) in &self.process_by_pid {
+            let known = self.process_identities.contains_key(pid);
+            if tracker.is_tracked(*pid) && (read_all || !known) {
+                if let Some(identity) = ProcessIdentity::read(process) {
+                    self.process_identities.insert(*pid, identity);
+                }
+            }
+        }
     }
 }
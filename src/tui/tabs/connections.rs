@@ -0,0 +1,115 @@
+use std::time::{Duration, Instant};
+
+use procfs::process::Process;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    bandwidth_tracker::bytes::{NumberOfBytes, UnitFormat},
+    system::sockets::{sockets_of_process, Socket},
+};
+
+/// Reading the sockets of every process is costly, they are not read on every tick
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct ConnectionRow {
+    pub pid: i32,
+    pub name: String,
+    pub socket: Socket,
+    /// (received, send) bytes since the connection was opened, only known for TCP with `sock_diag`
+    pub bytes: Option<(NumberOfBytes, NumberOfBytes)>,
+}
+
+/// Sockets of the processes seen by the probes
+#[derive(Default)]
+pub struct ConnectionsTab {
+    pub rows: Vec<ConnectionRow>,
+    pub state: TableState,
+    pub page_size: usize,
+    refreshed_at: Option<Instant>,
+}
+
+impl ConnectionsTab {
+    /// Reads the sockets of the (pid, name, process), at most once per `REFRESH_INTERVAL`
+    pub fn refresh<'a>(
+        &mut self,
+        processes: impl Iterator<Item = (i32, String, &'a Process)>,
+        bytes_of: impl Fn(u64) -> Option<(NumberOfBytes, NumberOfBytes)>,
+    ) {
+        if self
+            .refreshed_at
+            .is_some_and(|refreshed_at| refreshed_at.elapsed() < REFRESH_INTERVAL)
+        {
+            return;
+        }
+
+        self.rows = processes
+            .flat_map(|(pid, name, process)| {
+                sockets_of_process(process)
+                    .into_iter()
+                    .map(|socket| ConnectionRow {
+                        pid,
+                        name: name.clone(),
+                        bytes: bytes_of(socket.inode),
+                        socket,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        self.rows.sort_by_key(|row| (row.pid, row.socket.inode));
+        self.refreshed_at = Some(Instant::now());
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect, units: &UnitFormat) {
+        // borders and header
+        self.page_size = area.height.saturating_sub(3) as usize;
+
+        let rows = self.rows.iter().map(|row| {
+            Row::new([
+                row.pid.to_string(),
+                row.name.clone(),
+                row.socket.protocol.to_string(),
+                row.socket.local.to_string(),
+                row.socket.remote.to_string(),
+                row.socket.state.clone(),
+                row.bytes
+                    .map(|(received, _)| units.bytes(received))
+                    .unwrap_or_default(),
+                row.bytes
+                    .map(|(_, send)| units.bytes(send))
+                    .unwrap_or_default(),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(8),
+                Constraint::Fill(1),
+                Constraint::Length(5),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+                Constraint::Length(10),
+                Constraint::Length(10),
+            ],
+        )
+        .header(Row::new([
+            "pid", "name", "proto", "local", "remote", "state", "received", "send",
+        ]))
+        .block(
+            Block::bordered().title(
+                Line::from(format!("Connections ({})", self.rows.len()))
+                    .bold()
+                    .centered(),
+            ),
+        )
+        .row_highlight_style(Style::new().reversed());
+
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+}
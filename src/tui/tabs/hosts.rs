@@ -0,0 +1,88 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    net::IpAddr,
+};
+
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Row, Table, TableState},
+    Frame,
+};
+
+use super::connections::ConnectionRow;
+
+pub struct HostRow {
+    pub address: IpAddr,
+    pub connections: usize,
+    /// Names of the processes connected to the host
+    pub processes: BTreeSet<String>,
+}
+
+/// Remote hosts of the connections, the most connected first
+#[derive(Default)]
+pub struct HostsTab {
+    pub rows: Vec<HostRow>,
+    pub state: TableState,
+    pub page_size: usize,
+}
+
+impl HostsTab {
+    /// Groups the connections by remote address, the listening sockets have no remote host
+    pub fn refresh(&mut self, connections: &[ConnectionRow]) {
+        let mut hosts: BTreeMap<IpAddr, HostRow> = BTreeMap::new();
+        for connection in connections {
+            let address = connection.socket.remote.ip();
+            if address.is_unspecified() {
+                continue;
+            }
+            let host = hosts.entry(address).or_insert_with(|| HostRow {
+                address,
+                connections: 0,
+                processes: BTreeSet::new(),
+            });
+            host.connections += 1;
+            host.processes.insert(connection.name.clone());
+        }
+
+        self.rows = hosts.into_values().collect();
+        self.rows.sort_by(|a, b| {
+            b.connections
+                .cmp(&a.connections)
+                .then(a.address.cmp(&b.address))
+        });
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        // borders and header
+        self.page_size = area.height.saturating_sub(3) as usize;
+
+        let rows = self.rows.iter().map(|row| {
+            Row::new([
+                row.address.to_string(),
+                row.connections.to_string(),
+                row.processes.iter().cloned().collect::<Vec<_>>().join(", "),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(40),
+                Constraint::Length(11),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["remote host", "connections", "processes"]))
+        .block(
+            Block::bordered().title(
+                Line::from(format!("Hosts ({})", self.rows.len()))
+                    .bold()
+                    .centered(),
+            ),
+        )
+        .row_highlight_style(Style::new().reversed());
+
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+}
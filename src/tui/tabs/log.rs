@@ -0,0 +1,49 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Row, Table, TableState},
+    Frame,
+};
+
+/// Number of messages kept, the oldest are dropped first
+const LOG_SIZE: usize = 1000;
+
+/// Messages of the session: actions on processes and their outcome, the newest first
+#[derive(Default)]
+pub struct Log {
+    pub entries: VecDeque<(DateTime<Local>, String)>,
+    pub state: TableState,
+    pub page_size: usize,
+}
+
+impl Log {
+    pub fn push(&mut self, message: String) {
+        self.entries.push_front((Local::now(), message));
+        self.entries.truncate(LOG_SIZE);
+        // keep the cursor on the same message
+        if let Some(selected) = self.state.selected() {
+            self.state
+                .select(Some((selected + 1).min(self.entries.len() - 1)));
+        }
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect) {
+        // borders and header
+        self.page_size = area.height.saturating_sub(3) as usize;
+
+        let rows = self
+            .entries
+            .iter()
+            .map(|(at, message)| Row::new([at.format("%H:%M:%S").to_string(), message.clone()]));
+        let table = Table::new(rows, [Constraint::Length(8), Constraint::Fill(1)])
+            .header(Row::new(["time", "message"]))
+            .block(Block::bordered().title(Line::from("Log").bold().centered()))
+            .row_highlight_style(Style::new().reversed());
+
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+}
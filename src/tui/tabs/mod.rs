@@ -0,0 +1,110 @@
+use std::fmt::Display;
+
+use ratatui::widgets::TableState;
+
+use super::events::Event;
+
+pub mod connections;
+pub mod hosts;
+pub mod log;
+pub mod usage;
+
+/// Views of the TUI, one per tab of the tab bar
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tab {
+    #[default]
+    Processes,
+    Interfaces,
+    Connections,
+    Hosts,
+    Log,
+    Usage,
+}
+
+impl Tab {
+    /// Tabs in the order of the tab bar
+    pub const ALL: [Tab; 6] = [
+        Tab::Processes,
+        Tab::Interfaces,
+        Tab::Connections,
+        Tab::Hosts,
+        Tab::Log,
+        Tab::Usage,
+    ];
+
+    pub fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|tab| *tab == self)
+            .unwrap_or_default()
+    }
+
+    /// Next tab to the right, or to the left for a negative offset, wrapping around
+    pub fn cycle(self, offset: isize) -> Tab {
+        let index = (self.index() as isize + offset).rem_euclid(Self::ALL.len() as isize);
+        Self::ALL[index as usize]
+    }
+}
+
+impl Display for Tab {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let title = match self {
+            Tab::Processes => "Processes",
+            Tab::Interfaces => "Interfaces",
+            Tab::Connections => "Connections",
+            Tab::Hosts => "Hosts",
+            Tab::Log => "Log",
+            Tab::Usage => "Usage",
+        };
+        write!(f, "{title}")
+    }
+}
+
+/// Moves the cursor of a table of `len` rows for a navigation event, returns whether the event was handled
+pub fn navigate(state: &mut TableState, event: &Event, len: usize, page_size: usize) -> bool {
+    let last = len.saturating_sub(1);
+    let page_size = page_size.max(1);
+    let selected = state.selected();
+    let index = match event {
+        Event::Up | Event::ScrollUp => selected.map_or(0, |index| index.saturating_sub(1)),
+        Event::Down | Event::ScrollDown => selected.map_or(0, |index| index + 1),
+        Event::PageUp => selected.map_or(0, |index| index.saturating_sub(page_size)),
+        Event::PageDown => selected.map_or(0, |index| index + page_size),
+        Event::Home => 0,
+        Event::End => last,
+        Event::Back => {
+            state.select(None);
+            return true;
+        }
+        _ => return false,
+    };
+    state.select((len > 0).then_some(index.min(last)));
+    true
+}
+
+#[cfg(test)]
+mod tests_tabs {
+    use ratatui::widgets::TableState;
+
+    use crate::tui::events::Event;
+
+    use super::{navigate, Tab};
+
+    #[test]
+    fn test_cycle_wraps_around() {
+        assert_eq!(Tab::Processes.cycle(-1), Tab::Usage);
+        assert_eq!(Tab::Usage.cycle(1), Tab::Processes);
+    }
+
+    #[test]
+    fn test_navigate_stays_within_the_rows() {
+        let mut state = TableState::default();
+
+        navigate(&mut state, &Event::Down, 3, 10);
+        assert_eq!(state.selected(), Some(0));
+        navigate(&mut state, &Event::PageDown, 3, 10);
+        assert_eq!(state.selected(), Some(2));
+        navigate(&mut state, &Event::Down, 0, 10);
+        assert_eq!(state.selected(), None);
+    }
+}
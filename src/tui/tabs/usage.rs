@@ -0,0 +1,128 @@
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    bandwidth_tracker::{
+        bytes::{NumberOfBytes, UnitFormat},
+        quotas::{Budget, Counters, UsageAccounting, UsageSubject},
+    },
+    tui::theme::Theme,
+};
+
+pub struct UsageRow {
+    pub subject: UsageSubject,
+    pub today: Counters,
+    pub month: Counters,
+    /// The quota of the subject with the least data left, if any
+    pub budget: Option<Budget>,
+}
+
+/// Data used by the programs and the users today and this month, with what is left of their quotas
+#[derive(Default)]
+pub struct UsageTab {
+    pub rows: Vec<UsageRow>,
+    pub state: TableState,
+    pub page_size: usize,
+}
+
+impl UsageTab {
+    /// The most used first, the users after the programs
+    pub fn refresh(&mut self, accounting: &UsageAccounting) {
+        let mut budgets = accounting.budgets();
+        budgets.sort_by_key(|budget| budget.remaining());
+
+        let programs = accounting
+            .monthly
+            .programs
+            .keys()
+            .cloned()
+            .map(UsageSubject::Program);
+        let users = accounting
+            .monthly
+            .users
+            .keys()
+            .cloned()
+            .map(UsageSubject::User);
+        self.rows = programs
+            .chain(users)
+            .map(|subject| {
+                let budget = budgets
+                    .iter()
+                    .position(|budget| budget.subject == subject)
+                    .map(|index| budgets.remove(index));
+                UsageRow {
+                    today: accounting.daily.of(&subject),
+                    month: accounting.monthly.of(&subject),
+                    subject,
+                    budget,
+                }
+            })
+            .collect();
+        self.rows.sort_by(|a, b| {
+            let total = |row: &UsageRow| row.month.received + row.month.send;
+            matches!(a.subject, UsageSubject::User(_))
+                .cmp(&matches!(b.subject, UsageSubject::User(_)))
+                .then(total(b).cmp(&total(a)))
+        });
+    }
+
+    pub fn draw(&mut self, frame: &mut Frame, area: Rect, units: &UnitFormat, theme: &Theme) {
+        // borders and header
+        self.page_size = area.height.saturating_sub(3) as usize;
+
+        let used = |counters: &Counters| {
+            format!(
+                "{} / {}",
+                units.bytes(counters.received),
+                units.bytes(counters.send)
+            )
+        };
+        let rows = self.rows.iter().map(|row| {
+            let (quota, left) = match &row.budget {
+                Some(budget) => (
+                    format!("{} per {}", units.bytes(budget.limit), budget.period),
+                    units.bytes(budget.remaining()),
+                ),
+                None => Default::default(),
+            };
+            let row_style = match &row.budget {
+                Some(budget) if budget.remaining() == NumberOfBytes::default() => theme.problem,
+                _ => Style::new(),
+            };
+            Row::new([
+                row.subject.to_string(),
+                used(&row.today),
+                used(&row.month),
+                quota,
+                left,
+            ])
+            .style(row_style)
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new([
+            "program / user",
+            "today (received / send)",
+            "this month (received / send)",
+            "quota",
+            "left",
+        ]))
+        .block(Block::bordered().title(Line::from("Data usage").bold().centered()))
+        .row_highlight_style(Style::new().reversed());
+
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+}
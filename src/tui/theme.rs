@@ -0,0 +1,153 @@
+use ratatui::style::{Color, Style, Stylize};
+
+use crate::{bandwidth_tracker::bytes::BytesPerSecond, config::ThemeName};
+
+/// Colors and styles of the TUI
+pub struct Theme {
+    /// Colors of the series of the charts, cycled through
+    pub chart: Vec<Color>,
+    pub paused: Style,
+    pub error: Style,
+    pub message: Style,
+    /// Interfaces that are down or dropping packets
+    pub problem: Style,
+    /// Rows of the processes and interfaces breaking an alert rule
+    pub alert: Style,
+    /// Rows of the processes above each heat threshold, from the coolest to the hottest
+    pub heat: [Style; 3],
+}
+
+impl Theme {
+    /// The named theme, `NO_COLOR` turns the default theme into the monochrome one
+    pub fn new(name: Option<ThemeName>) -> Theme {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        match name {
+            Some(name) => Theme::named(name),
+            None if no_color => Theme::named(ThemeName::Monochrome),
+            None => Theme::named(ThemeName::Dark),
+        }
+    }
+
+    fn named(name: ThemeName) -> Theme {
+        match name {
+            ThemeName::Dark => Theme {
+                chart: vec![
+                    Color::LightBlue,
+                    Color::LightYellow,
+                    Color::LightGreen,
+                    Color::LightMagenta,
+                    Color::LightCyan,
+                    Color::LightRed,
+                    Color::Blue,
+                    Color::Yellow,
+                    Color::Green,
+                    Color::Magenta,
+                    Color::Cyan,
+                    Color::Red,
+                ],
+                paused: Style::new().light_yellow(),
+                error: Style::new().light_red(),
+                message: Style::new().light_cyan(),
+                problem: Style::new().light_red(),
+                alert: Style::new().white().on_red().bold(),
+                heat: [
+                    Style::new().yellow(),
+                    Style::new().light_red(),
+                    Style::new().red().bold(),
+                ],
+            },
+            ThemeName::Light => Theme {
+                chart: vec![
+                    Color::Blue,
+                    Color::Red,
+                    Color::Green,
+                    Color::Magenta,
+                    Color::Cyan,
+                    Color::Indexed(130),
+                    Color::Indexed(18),
+                    Color::Indexed(88),
+                    Color::Indexed(22),
+                    Color::Indexed(90),
+                    Color::Indexed(23),
+                    Color::Black,
+                ],
+                paused: Style::new().magenta(),
+                error: Style::new().red(),
+                message: Style::new().blue(),
+                problem: Style::new().red(),
+                alert: Style::new().white().on_red().bold(),
+                heat: [
+                    Style::new().fg(Color::Indexed(130)),
+                    Style::new().red(),
+                    Style::new().red().bold(),
+                ],
+            },
+            ThemeName::HighContrast => Theme {
+                chart: vec![
+                    Color::White,
+                    Color::LightYellow,
+                    Color::LightCyan,
+                    Color::LightMagenta,
+                    Color::LightGreen,
+                    Color::LightRed,
+                ],
+                paused: Style::new().black().on_light_yellow(),
+                error: Style::new().white().on_red().bold(),
+                message: Style::new().black().on_light_cyan(),
+                problem: Style::new().white().on_red().bold(),
+                alert: Style::new().black().on_light_red().bold(),
+                heat: [
+                    Style::new().light_yellow().bold(),
+                    Style::new().light_red().bold(),
+                    Style::new().white().on_red().bold(),
+                ],
+            },
+            ThemeName::Monochrome => Theme {
+                chart: vec![Color::Reset],
+                paused: Style::new().bold(),
+                error: Style::new().bold().underlined(),
+                message: Style::new().italic(),
+                problem: Style::new().bold().underlined(),
+                alert: Style::new().bold().italic().underlined(),
+                heat: [
+                    Style::new().bold(),
+                    Style::new().bold().italic(),
+                    Style::new().bold().underlined(),
+                ],
+            },
+        }
+    }
+
+    /// Style of a process row, the hotter the more `thresholds` the rate is above
+    pub fn heat_of(&self, rate: BytesPerSecond, thresholds: &[BytesPerSecond]) -> Style {
+        let level = thresholds
+            .iter()
+            .filter(|threshold| rate >= **threshold)
+            .count();
+        match level {
+            0 => Style::new(),
+            level => self.heat[(level - 1).min(self.heat.len() - 1)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_theme {
+    use ratatui::style::Style;
+
+    use crate::bandwidth_tracker::bytes::BytesPerSecond;
+
+    use crate::config::ThemeName;
+
+    use super::Theme;
+
+    #[test]
+    fn test_heat_levels() {
+        let theme = Theme::named(ThemeName::Dark);
+        let thresholds = [10., 100., 1000.].map(BytesPerSecond::from);
+
+        assert_eq!(theme.heat_of(5.0.into(), &thresholds), Style::new());
+        assert_eq!(theme.heat_of(10.0.into(), &thresholds), theme.heat[0]);
+        assert_eq!(theme.heat_of(5000.0.into(), &thresholds), theme.heat[2]);
+    }
+}